@@ -2,7 +2,7 @@
 //   cargo run --example bevy_ui
 
 use bevy::prelude::*;
-use bevy_blur_regions::{prelude::*, BlurRegionSettings};
+use bevy_blur_regions::{prelude::*, BlurRegionMode, BlurRegionSettings};
 
 #[path = "./utils.rs"]
 mod utils;
@@ -53,7 +53,7 @@ fn setup(mut commands: Commands) {
         MovingRegion,
         BlurRegionSettings {
             // opacity: 0.1,
-            blur_only: 1.0,
+            mode: BlurRegionMode::BlurOnly,
             ..Default::default()
         },
         //BorderColor::all(Color::BLACK),
@@ -1,15 +1,113 @@
 use bevy::prelude::*;
 use bevy::camera::NormalizedRenderTarget;
+use bevy::ui::InteractionDisabled;
 use bevy::window::PrimaryWindow;
 
 use crate::BlurRegion;
+use crate::BlurRegionSettings;
 use crate::BlurRegionsCamera;
 
 pub struct BlurRegionsBevyUiPlugin;
 
 impl Plugin for BlurRegionsBevyUiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Last, crate::bevy_ui::compute_blur_regions);
+        app.register_type::<BlurRegionInteractionSettings>();
+        app.add_systems(Last, (animate_interaction_settings, crate::bevy_ui::compute_blur_regions).chain());
+    }
+}
+
+/// Glass settings for each [`Interaction`] state of a Bevy UI node with a [`BlurRegion`], such as
+/// a button. The node's [`BlurRegionSettings`] are animated towards the settings of its state.
+#[derive(Component, Reflect, Clone, Copy)]
+#[reflect(Component, Default)]
+#[require(BlurRegionSettings, BlurRegionInteractionTransition, Interaction)]
+pub struct BlurRegionInteractionSettings {
+    /// Used while the node is not hovered or pressed.
+    pub normal: BlurRegionSettings,
+    /// Used while the node is hovered. When `None`, `normal` is used.
+    pub hovered: Option<BlurRegionSettings>,
+    /// Used while the node is pressed. When `None`, `hovered` is used.
+    pub pressed: Option<BlurRegionSettings>,
+    /// Used while the node has [`InteractionDisabled`]. When `None`, `normal` is used.
+    pub disabled: Option<BlurRegionSettings>,
+    /// How long moving to the settings of a new state takes, in seconds.
+    pub transition_duration: f32,
+}
+
+impl Default for BlurRegionInteractionSettings {
+    fn default() -> Self {
+        Self {
+            normal: BlurRegionSettings::default(),
+            hovered: None,
+            pressed: None,
+            disabled: None,
+            transition_duration: 0.15,
+        }
+    }
+}
+
+impl BlurRegionInteractionSettings {
+    fn settings(&self, state: GlassState) -> BlurRegionSettings {
+        let hovered = self.hovered.unwrap_or(self.normal);
+        match state {
+            GlassState::Normal => self.normal,
+            GlassState::Hovered => hovered,
+            GlassState::Pressed => self.pressed.unwrap_or(hovered),
+            GlassState::Disabled => self.disabled.unwrap_or(self.normal),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum GlassState {
+    Normal,
+    Hovered,
+    Pressed,
+    Disabled,
+}
+
+/// The transition of a node with [`BlurRegionInteractionSettings`] into its current state.
+#[derive(Component, Default)]
+pub struct BlurRegionInteractionTransition {
+    /// `None` until the node has been animated once.
+    state: Option<GlassState>,
+    from: BlurRegionSettings,
+    elapsed: f32,
+}
+
+/// The nodes whose glass follows their interaction state.
+type InteractionSettingsQueryData = (
+    Ref<'static, BlurRegionInteractionSettings>,
+    &'static mut BlurRegionSettings,
+    &'static mut BlurRegionInteractionTransition,
+    &'static Interaction,
+    Has<InteractionDisabled>,
+);
+
+fn animate_interaction_settings(time: Res<Time<Real>>, mut nodes: Query<InteractionSettingsQueryData>) {
+    for (interaction_settings, mut settings, mut transition, interaction, disabled) in &mut nodes {
+        let state = match (disabled, interaction) {
+            (true, _) => GlassState::Disabled,
+            (false, Interaction::Pressed) => GlassState::Pressed,
+            (false, Interaction::Hovered) => GlassState::Hovered,
+            (false, Interaction::None) => GlassState::Normal,
+        };
+        let duration = interaction_settings.transition_duration;
+        let target = interaction_settings.settings(state);
+        if transition.state != Some(state) {
+            // New nodes start out in their state instead of fading in from the default settings
+            transition.from = if transition.state.is_some() { *settings } else { target };
+            transition.state = Some(state);
+            transition.elapsed = 0.0;
+        } else if transition.elapsed >= duration && !interaction_settings.is_changed() {
+            // Leave finished transitions alone, so the settings are not marked as changed every frame
+            continue;
+        }
+        transition.elapsed += time.delta_secs();
+
+        let t = if duration > 0.0 { (transition.elapsed / duration).min(1.0) } else { 1.0 };
+        let t = t * t * (3.0 - 2.0 * t);
+        *settings = transition.from.lerp(&target, t);
     }
 }
 
@@ -38,7 +136,6 @@ pub fn compute_blur_regions(
 
         let mut sorted_nodes: Vec<_> = nodes.iter().collect();
         sorted_nodes.sort_by_key(|(node, ..)| node.stack_index);
-        sorted_nodes.reverse();
 
         for (node, transform, border_radius, visibility, settings) in sorted_nodes {
             if visibility.get() == false && false {
@@ -59,7 +156,7 @@ pub fn compute_blur_regions(
                 border_radius.bottom_left,
             ]
             .map(|v| v.resolve(window.scale_factor(), node.size().y, window.physical_size().as_vec2()).unwrap_or(0.0));
-            blur_regions.rounded_blur(region, bevy::prelude::Vec4::from_array(resolved), region_settings);
+            blur_regions.push_region(region, bevy::prelude::Vec4::from_array(resolved), region_settings, node.stack_index);
         }
     }
 }
@@ -0,0 +1,300 @@
+use std::ops::Range;
+
+use bevy::core_pipeline::core_2d::graph::Core2d;
+use bevy::core_pipeline::core_3d::graph::Core3d;
+use bevy::ecs::query::QueryState;
+use bevy::math::FloatOrd;
+use bevy::prelude::*;
+use bevy::render::camera::ExtractedCamera;
+use bevy::render::diagnostic::RecordDiagnostics;
+use bevy::render::render_graph::Node;
+use bevy::render::render_graph::NodeRunError;
+use bevy::render::render_graph::RenderGraph;
+use bevy::render::render_graph::RenderGraphContext;
+use bevy::render::render_graph::RenderSubGraph;
+use bevy::render::render_phase::Draw;
+use bevy::render::render_phase::DrawError;
+use bevy::render::render_phase::DrawFunctions;
+use bevy::render::render_phase::PhaseItem;
+use bevy::render::render_phase::PhaseItemExtraIndex;
+use bevy::render::render_phase::SortedRenderPhase;
+use bevy::render::render_phase::TrackedRenderPass;
+use bevy::render::render_phase::ViewSortedRenderPhases;
+use bevy::render::render_resource::CachedRenderPipelineId;
+use bevy::render::render_resource::RenderPassDescriptor;
+use bevy::render::renderer::RenderContext;
+use bevy::render::sync_world::MainEntity;
+use bevy::render::view::ExtractedView;
+use bevy::render::view::ViewTarget;
+use bevy::render::Render;
+use bevy::render::RenderApp;
+use bevy::render::RenderSystems;
+use bevy::ui_render::graph::NodeUi;
+use bevy::ui_render::graph::SubGraphUi;
+use bevy::ui_render::stack_z_offsets;
+use bevy::ui_render::TransparentUi;
+use bevy::ui_render::UiCameraView;
+use bevy::ui_render::UiViewTarget;
+
+use crate::shader::create_intermediate_view;
+use crate::shader::render_blur_regions_layer;
+use crate::shader::BlurRegionsPasses;
+use crate::shader::ViewBlurRegionsFullscreen;
+use crate::shader::ViewBlurRegionsLayers;
+use crate::BlurRegionsCamera;
+
+/// Glass layers are drawn after the box shadow of their lowest node, but before its background.
+const GLASS_LAYER_Z_OFFSET: f32 = (stack_z_offsets::BOX_SHADOW + stack_z_offsets::BACKGROUND_COLOR) * 0.5;
+
+/// Draws the glass of cameras with [`BlurRegionsCamera::render_in_ui_pass`] set in between the
+/// UI nodes, so that glass blurs the UI beneath it.
+pub struct BlurRegionsUiRenderPlugin;
+
+impl Plugin for BlurRegionsUiRenderPlugin {
+    fn build(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.add_systems(Render, queue_blur_regions_layers.in_set(RenderSystems::Queue));
+    }
+
+    fn finish(&self, app: &mut App) {
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.world().resource::<DrawFunctions<TransparentUi>>().write().add(DrawBlurRegionsLayer);
+
+        // Replace the UI pass of both core graphs with one that can stop to draw the glass
+        let ui_pass_2d = BlurRegionsUiPassNode::new(render_app.world_mut());
+        let ui_pass_3d = BlurRegionsUiPassNode::new(render_app.world_mut());
+        let mut graph = render_app.world_mut().resource_mut::<RenderGraph>();
+        for (core_graph, ui_pass) in [
+            (Core2d.intern(), ui_pass_2d),
+            (Core3d.intern(), ui_pass_3d),
+        ] {
+            if let Some(ui_graph) = graph
+                .get_sub_graph_mut(core_graph)
+                .and_then(|core_graph| core_graph.get_sub_graph_mut(SubGraphUi))
+            {
+                ui_graph.add_node(NodeUi::UiPass, ui_pass);
+            }
+        }
+    }
+}
+
+/// Marks the point in a UI phase where the next glass layer is drawn. It draws nothing itself.
+struct DrawBlurRegionsLayer;
+
+impl Draw<TransparentUi> for DrawBlurRegionsLayer {
+    fn draw<'w>(
+        &mut self,
+        _world: &'w World,
+        _pass: &mut TrackedRenderPass<'w>,
+        _view: Entity,
+        _item: &TransparentUi,
+    ) -> Result<(), DrawError> {
+        Ok(())
+    }
+}
+
+fn queue_blur_regions_layers(
+    draw_functions: Res<DrawFunctions<TransparentUi>>,
+    mut transparent_render_phases: ResMut<ViewSortedRenderPhases<TransparentUi>>,
+    cameras: Query<(Entity, &MainEntity, &BlurRegionsCamera, &UiCameraView)>,
+    ui_views: Query<&ExtractedView>,
+) {
+    let draw_function = draw_functions.read().id::<DrawBlurRegionsLayer>();
+
+    for (entity, main_entity, camera, ui_camera_view) in &cameras {
+        if !camera.render_in_ui_pass {
+            continue;
+        }
+        let Ok(ui_view) = ui_views.get(ui_camera_view.0) else {
+            continue;
+        };
+        let Some(transparent_phase) = transparent_render_phases.get_mut(&ui_view.retained_view_entity) else {
+            continue;
+        };
+
+        // The markers are not UI nodes, so they also stop the UI from batching across a layer
+        for stack_index in camera.layer_stack_indices() {
+            transparent_phase.add(TransparentUi {
+                sort_key: FloatOrd(stack_index as f32 + GLASS_LAYER_Z_OFFSET),
+                entity: (entity, *main_entity),
+                pipeline: CachedRenderPipelineId::INVALID,
+                draw_function,
+                batch_range: 0..0,
+                extra_index: PhaseItemExtraIndex::None,
+                index: usize::MAX,
+                indexed: false,
+            });
+        }
+    }
+}
+
+/// The camera a UI view renders to, along with its glass if it has any.
+type UiViewTargetQueryData = (
+    &'static ViewTarget,
+    &'static ExtractedCamera,
+    Option<&'static BlurRegionsCamera>,
+    Option<&'static BlurRegionsPasses>,
+    Option<&'static ViewBlurRegionsLayers>,
+    Option<&'static ViewBlurRegionsFullscreen>,
+);
+
+/// A drop-in replacement for Bevy's UI pass node that renders the UI phase in ranges, drawing a
+/// glass layer of the camera at each layer marker. Cameras without glass in the UI pass render in
+/// a single pass, just like with Bevy's node.
+struct BlurRegionsUiPassNode {
+    ui_view_query: QueryState<(&'static ExtractedView, &'static UiViewTarget)>,
+    ui_view_target_query: QueryState<UiViewTargetQueryData>,
+    ui_camera_view_query: QueryState<&'static UiCameraView>,
+}
+
+impl BlurRegionsUiPassNode {
+    fn new(world: &mut World) -> Self {
+        Self {
+            ui_view_query: world.query_filtered(),
+            ui_view_target_query: world.query(),
+            ui_camera_view_query: world.query(),
+        }
+    }
+}
+
+impl Node for BlurRegionsUiPassNode {
+    fn update(&mut self, world: &mut World) {
+        self.ui_view_query.update_archetypes(world);
+        self.ui_view_target_query.update_archetypes(world);
+        self.ui_camera_view_query.update_archetypes(world);
+    }
+
+    fn run(
+        &self,
+        graph: &mut RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), NodeRunError> {
+        let input_view_entity = graph.view_entity();
+
+        let Some(transparent_render_phases) = world.get_resource::<ViewSortedRenderPhases<TransparentUi>>() else {
+            return Ok(());
+        };
+
+        let Ok((view, ui_view_target)) = self.ui_view_query.get_manual(world, input_view_entity) else {
+            return Ok(());
+        };
+
+        let Ok((target, camera, blur_regions_camera, passes, layers, fullscreen)) =
+            self.ui_view_target_query.get_manual(world, ui_view_target.0)
+        else {
+            return Ok(());
+        };
+
+        let Some(transparent_phase) = transparent_render_phases.get(&view.retained_view_entity) else {
+            return Ok(());
+        };
+
+        let ui_pass_glass = blur_regions_camera.is_some_and(|camera| camera.render_in_ui_pass);
+        let passes = passes.filter(|_| ui_pass_glass);
+        let mut intermediate_view = None;
+
+        // The full-screen blur goes beneath all of the UI, so it is drawn even without any UI
+        if let (Some(passes), Some(fullscreen)) = (passes, fullscreen) {
+            let intermediate_view =
+                intermediate_view.get_or_insert_with(|| create_intermediate_view(render_context, target));
+            render_blur_regions_layer(render_context, world, target, passes, ui_view_target.0, intermediate_view, fullscreen.0);
+        }
+
+        if transparent_phase.items.is_empty() {
+            return Ok(());
+        }
+
+        // use the UI view entity if it is defined
+        let view_entity = if let Ok(ui_camera_view) = self.ui_camera_view_query.get_manual(world, input_view_entity) {
+            ui_camera_view.0
+        } else {
+            input_view_entity
+        };
+
+        let Some(passes) = passes else {
+            render_ui_range(render_context, world, target, camera, view_entity, transparent_phase, None);
+            return Ok(());
+        };
+
+        let layer_draw_function = world.resource::<DrawFunctions<TransparentUi>>().read().id::<DrawBlurRegionsLayer>();
+
+        let mut layer = 0;
+        let mut range_start = 0;
+
+        for (index, item) in transparent_phase.items.iter().enumerate() {
+            if item.draw_function() != layer_draw_function {
+                continue;
+            }
+
+            let range = range_start..index;
+            render_ui_range(render_context, world, target, camera, view_entity, transparent_phase, Some(range));
+            range_start = index + 1;
+
+            if let Some(settings_offset) = layers.and_then(|layers| layers.0.get(layer)) {
+                let intermediate_view =
+                    intermediate_view.get_or_insert_with(|| create_intermediate_view(render_context, target));
+                render_blur_regions_layer(
+                    render_context,
+                    world,
+                    target,
+                    passes,
+                    ui_view_target.0,
+                    intermediate_view,
+                    *settings_offset,
+                );
+            }
+            layer += 1;
+        }
+
+        let range = range_start..transparent_phase.items.len();
+        render_ui_range(render_context, world, target, camera, view_entity, transparent_phase, Some(range));
+
+        Ok(())
+    }
+}
+
+/// Renders the given range of the UI phase, or all of it, in its own render pass. Draw errors
+/// are logged rather than failing the graph, like Bevy's UI pass does.
+fn render_ui_range(
+    render_context: &mut RenderContext,
+    world: &World,
+    target: &ViewTarget,
+    camera: &ExtractedCamera,
+    view_entity: Entity,
+    transparent_phase: &SortedRenderPhase<TransparentUi>,
+    range: Option<Range<usize>>,
+) {
+    if range.as_ref().is_some_and(|range| range.is_empty()) {
+        return;
+    }
+
+    let diagnostics = render_context.diagnostic_recorder();
+    let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some("ui"),
+        color_attachments: &[Some(target.get_unsampled_color_attachment())],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    let pass_span = diagnostics.pass_span(&mut render_pass, "ui");
+
+    if let Some(viewport) = camera.viewport.as_ref() {
+        render_pass.set_camera_viewport(viewport);
+    }
+    let result = match range {
+        Some(range) => transparent_phase.render_range(&mut render_pass, world, view_entity, range),
+        None => transparent_phase.render(&mut render_pass, world, view_entity),
+    };
+    if let Err(err) = result {
+        error!("Error encountered while rendering the ui phase {err:?}");
+    }
+
+    pass_span.end(&mut render_pass);
+}
@@ -19,6 +19,20 @@
     @group(0) @binding(2) var texture_sampler: sampler;
     @group(0) @binding(3) var<uniform> settings: GpuBlurRegionsSettings;
     @group(0) @binding(4) var<storage, read> blur_regions: array<ComputedBlurRegion>;
+    @group(0) @binding(5) var environment_map: texture_cube<f32>;
+    @group(0) @binding(6) var environment_sampler: sampler;
+    @group(0) @binding(7) var normal_map_0: texture_2d<f32>;
+    @group(0) @binding(8) var normal_map_1: texture_2d<f32>;
+    @group(0) @binding(9) var normal_map_2: texture_2d<f32>;
+    @group(0) @binding(10) var normal_map_3: texture_2d<f32>;
+    @group(0) @binding(11) var normal_map_sampler: sampler;
+    @group(0) @binding(12) var frost_map_0: texture_2d<f32>;
+    @group(0) @binding(13) var frost_map_1: texture_2d<f32>;
+    @group(0) @binding(14) var frost_map_2: texture_2d<f32>;
+    @group(0) @binding(15) var frost_map_3: texture_2d<f32>;
+    @group(0) @binding(16) var frost_map_sampler: sampler;
+    @group(0) @binding(17) var frost_backdrop: texture_2d<f32>;
+    @group(0) @binding(18) var frost_backdrop_sampler: sampler;
 #else // HORIZONTAL_PASS
     // Bindings for the HORIZONTAL pass
     @group(0) @binding(0) var screen_texture: texture_2d<f32>;
@@ -29,7 +43,33 @@
 
 struct GpuBlurRegionsSettings {
     circle_of_confusion: f32,
+    regions_offset: u32,
     regions_count: u32,
+    // Only regions in this layer are drawn by the current pair of passes
+    layer: u32,
+    // Brightness of a full strength highlight in HDR lighting mode
+    highlight_scale: f32,
+    // Zero when the camera has no environment map to reflect
+    environment_intensity: f32,
+    // Rotates view space reflections into the space of the environment map
+    environment_transform: mat4x4<f32>,
+    // When set, the passes blur the whole view and the regions are ignored
+    fullscreen_tint: vec4<f32>,
+    fullscreen: u32,
+    fullscreen_dim: f32,
+    // How many standard deviations the Gaussian kernel reaches on each side
+    support_ratio: f32,
+    // Seconds since startup, wrapped to keep precision
+    time: f32,
+    ripple_duration: f32,
+    ripples_count: u32,
+    // Position of each ripple in xy and its age in z
+    ripples: array<vec4<f32>, MAX_RIPPLES>,
+    // In pixels, when the light is at a point such as the cursor
+    light_position: vec2<f32>,
+    point_light: u32,
+    point_light_radius: f32,
+    point_light_intensity: f32,
 }
 
 struct ComputedBlurRegion {
@@ -50,7 +90,63 @@ struct ComputedBlurRegion {
     specular_intensity: f32,
     reflection_shininess: f32,
     opacity: f32,
-    blur_only: f32,
+    mode: u32,
+    layer: u32,
+    stack_index: u32,
+    emissive_intensity: f32,
+    saturation: f32,
+    contrast: f32,
+    hue_rotation: f32,
+    grain_intensity: f32,
+    grain_size: f32,
+    dispersion: f32,
+    thickness: f32,
+    ior: f32,
+    bevel_width: f32,
+    bevel_profile: u32,
+    reflectivity: f32,
+    drop_shadow_color: vec4<f32>,
+    drop_shadow_offset: vec2<f32>,
+    drop_shadow_blur: f32,
+    drop_shadow_spread: f32,
+    stroke_start_color: vec4<f32>,
+    stroke_end_color: vec4<f32>,
+    inner_glow_color: vec4<f32>,
+    stroke_width: f32,
+    stroke_angle: f32,
+    inner_glow_width: f32,
+    mask_kind: u32,
+    // The angle of a linear mask in x, or the center of a radial one
+    mask_direction: vec2<f32>,
+    // Position and opacity of each stop
+    mask_start: vec2<f32>,
+    mask_end: vec2<f32>,
+    progressive_blur: u32,
+    progressive_blur_angle: f32,
+    progressive_blur_start: f32,
+    progressive_blur_end: f32,
+    ripple_amplitude: f32,
+    ripple_frequency: f32,
+    ripple_speed: f32,
+    wobble_amplitude: f32,
+    wobble_frequency: f32,
+    wobble_speed: f32,
+    // Nonzero when the region melts together with the others of its glass group
+    group: u32,
+    group_blend_radius: f32,
+    // Shapes cut out of the region, with the center of each relative to the region in xy and its half size in zw
+    hole_kinds: vec4<u32>,
+    hole_radii: vec4<f32>,
+    holes: array<vec4<f32>, MAX_HOLES>,
+    // The magnification and distortion of a lens in xy, the block size of a mosaic in x, or the color of a redaction
+    mode_params: vec4<f32>,
+    // One more than the slot of the normal map of the region, or 0 for none
+    normal_map: u32,
+    normal_map_tile_size: f32,
+    normal_map_rotation: f32,
+    normal_map_strength: f32,
+    // One more than the slot of the frost map of the region, or 0 for none
+    frost_map: u32,
 }
 
 const BLUR_SIZE: f32 = 50.;
@@ -63,19 +159,36 @@ const BORDER_INSET_PX: f32 = 1.0;
 
 const SHADOW_DISTANCE_PX: f32 = 40.0;
 
+// How far the steepest normal of a normal map moves the backdrop, in pixels
+const NORMAL_MAP_REFRACTION_PX: f32 = 12.0;
+// How much of the specular highlight the texture of a normal map catches across the glass
+const NORMAL_MAP_SHEEN: f32 = 0.15;
+
 const LIGHT_ADAPTIVITY: f32 = 1.0;
 const LIGHT_SOURCE_POS: vec2<f32> = vec2(0.25, -0.1);
 const MAX_REGIONS: u32 = 50u;
-
-fn get_normal(p: vec2<f32>, half_size: vec2<f32>, radii: vec4<f32>) -> vec2<f32> {
-    let epsilon = vec2(0.001, 0.0);
-    let grad_x = sd_rounded_box_per_corner(p + epsilon.xy, half_size, radii) - sd_rounded_box_per_corner(p - epsilon.xy, half_size, radii);
-    let grad_y = sd_rounded_box_per_corner(p + epsilon.yx, half_size, radii) - sd_rounded_box_per_corner(p - epsilon.yx, half_size, radii);
-    return normalize(vec2(grad_x, grad_y));
-}
+const MAX_RIPPLES: u32 = 8u;
+// Must match MAX_BLUR_REGION_HOLES, which is also the size of the hole_kinds and hole_radii vectors
+const MAX_HOLES: u32 = 4u;
 
 const PI = 3.14159265;
 
+const BEVEL_CIRCULAR: u32 = 0u;
+const BEVEL_CONVEX_SQUIRCLE: u32 = 1u;
+const BEVEL_CONCAVE_LIP: u32 = 2u;
+
+const MASK_LINEAR: u32 = 1u;
+const MASK_RADIAL: u32 = 2u;
+
+const MODE_GLASS: u32 = 0u;
+const MODE_BLUR_ONLY: u32 = 1u;
+const MODE_MAGNIFIER: u32 = 2u;
+const MODE_PIXELATE: u32 = 3u;
+const MODE_REDACT: u32 = 4u;
+
+const HOLE_ROUNDED_RECT: u32 = 1u;
+const HOLE_ELLIPSE: u32 = 2u;
+
 fn px(val: f32, resolution: vec2<f32>) -> f32 {
     return val / resolution.y;
 }
@@ -104,15 +217,286 @@ fn sd_rounded_box_per_corner(p: vec2<f32>, size: vec2<f32>, radii: vec4<f32>) ->
     return min(max(q.x, q.y), 0.0) + length(max(q, vec2(0.0))) - r;
 }
 
-fn create_masks(p: vec2<f32>, half_size: vec2<f32>, radii: vec4<f32>, resolution: vec2<f32>, displacement_falloff_width: f32, displacement_falloff_start: f32) -> vec3<f32> {
-    // let dist = sd_rounded_box_per_corner(p, half_size, radii);
-    let dist = sd_rounded_box_per_corner(p, half_size, radii) + px(BORDER_INSET_PX, resolution);
+// Approximate signed distance to an ellipse, which is close enough near its edge for the masks
+fn sd_ellipse(p: vec2<f32>, radii: vec2<f32>) -> f32 {
+    let r = max(radii, vec2(0.001));
+    let k0 = length(p / r);
+    let k1 = length(p / (r * r));
+    if (k1 == 0.0) {
+        return -min(r.x, r.y);
+    }
+    return k0 * (k0 - 1.0) / k1;
+}
+
+// Polynomial smooth minimum, which rounds off the crease where two distances meet over `k`
+fn smooth_min(a: f32, b: f32, k: f32) -> f32 {
+    if (k <= 0.0) {
+        return min(a, b);
+    }
+    let h = max(k - abs(a - b), 0.0) / k;
+    return min(a, b) - h * h * k * 0.25;
+}
+
+// Signed distance from a pixel to the edge of a region on its own, with its holes cut out, in pixels
+fn region_sdf(position: vec2<f32>, region: ComputedBlurRegion) -> f32 {
+    let center = vec2((region.max_x + region.min_x) * 0.5, (region.max_y + region.min_y) * 0.5);
+    let half_size = vec2(region.max_x - region.min_x, region.max_y - region.min_y) * 0.5;
+    var dist = sd_rounded_box_per_corner(position - center, half_size, region.border_radii);
+
+    for (var i = 0u; i < MAX_HOLES; i += 1u) {
+        let hole = region.holes[i];
+        let p = position - center - hole.xy;
+        var hole_dist: f32;
+        if (region.hole_kinds[i] == HOLE_ROUNDED_RECT) {
+            hole_dist = sd_rounded_box_per_corner(p, hole.zw, vec4(min(region.hole_radii[i], min(hole.z, hole.w))));
+        } else if (region.hole_kinds[i] == HOLE_ELLIPSE) {
+            hole_dist = sd_ellipse(p, hole.zw);
+        } else {
+            continue;
+        }
+        dist = max(dist, -hole_dist);
+    }
+    return dist;
+}
+
+// Signed distance from a pixel to the edge of the glass a region is part of, in pixels. That is
+// the region itself, or the smooth union of every region of its group in the current layer.
+fn shape_sdf(position: vec2<f32>, region: ComputedBlurRegion) -> f32 {
+    if (region.group == 0u) {
+        return region_sdf(position, region);
+    }
+    var dist = 1e10;
+    for (var i = 0u; i < settings.regions_count; i += 1u) {
+        let other = blur_regions[settings.regions_offset + i];
+        if (other.group != region.group || other.opacity == 0.0 || other.layer != settings.layer) {
+            continue;
+        }
+        dist = smooth_min(dist, region_sdf(position, other), region.group_blend_radius);
+    }
+    return dist;
+}
+
+// Direction in which the distance to the edge of the glass grows, in screen space
+fn shape_normal(position: vec2<f32>, region: ComputedBlurRegion) -> vec2<f32> {
+    let epsilon = vec2(0.5, 0.0);
+    let grad_x = shape_sdf(position + epsilon.xy, region) - shape_sdf(position - epsilon.xy, region);
+    let grad_y = shape_sdf(position + epsilon.yx, region) - shape_sdf(position - epsilon.yx, region);
+    return normalize(vec2(grad_x, grad_y));
+}
+
+// The bounds of the glass a region is part of, as the min in xy and the max in zw
+fn shape_bounds(region: ComputedBlurRegion) -> vec4<f32> {
+    var bounds = vec4(region.min_x, region.min_y, region.max_x, region.max_y);
+    if (region.group == 0u) {
+        return bounds;
+    }
+    for (var i = 0u; i < settings.regions_count; i += 1u) {
+        let other = blur_regions[settings.regions_offset + i];
+        if (other.group != region.group || other.opacity == 0.0 || other.layer != settings.layer) {
+            continue;
+        }
+        bounds = vec4(min(bounds.xy, vec2(other.min_x, other.min_y)), max(bounds.zw, vec2(other.max_x, other.max_y)));
+    }
+    return bounds;
+}
+
+// Broad-phase check of whether a pixel can be in a region, including where its group bulges out
+fn in_region_bounds(position: vec2<f32>, region: ComputedBlurRegion) -> bool {
+    let reach = select(0.0, max(region.group_blend_radius, 0.0) * 0.25, region.group != 0u);
+    return position.x >= region.min_x - reach && position.x <= region.max_x + reach &&
+        position.y >= region.min_y - reach && position.y <= region.max_y + reach;
+}
+
+// Whether a region is the lowest of its group in the current layer, so that the group is only
+// counted once when walking the regions.
+fn is_group_leader(index: u32, region: ComputedBlurRegion) -> bool {
+    for (var i = 0u; i < index; i += 1u) {
+        let other = blur_regions[settings.regions_offset + i];
+        if (other.group == region.group && other.opacity != 0.0 && other.layer == settings.layer) {
+            return false;
+        }
+    }
+    return true;
+}
+
+// `dist_to_edge` is the distance to the edge of the glass, divided by the height of the view
+fn create_masks(dist_to_edge: f32, resolution: vec2<f32>, displacement_falloff_width: f32, displacement_falloff_start: f32) -> vec3<f32> {
+    let dist = dist_to_edge + px(BORDER_INSET_PX, resolution);
     let box_shape = smoothstep(px(BORDER_SHARPNESS_PX, resolution), 0.0, dist);
     let box_disp = smoothstep(px(displacement_falloff_width, resolution), 0.0, dist + px(displacement_falloff_start, resolution));
     let box_light = box_shape * smoothstep(0.0, px(30.0, resolution), dist + px(10.0, resolution));
     return vec3<f32>(box_shape, box_disp, box_light);
 }
 
+// Height of the bevel, from 0.0 at the edge to 1.0 where the glass is flat. `x` goes the same way.
+fn bevel_height(x: f32, profile: u32) -> f32 {
+    let t = 1.0 - clamp(x, 0.0, 1.0);
+    if (profile == BEVEL_CONVEX_SQUIRCLE) {
+        return pow(1.0 - pow(t, 4.0), 0.25);
+    } else if (profile == BEVEL_CONCAVE_LIP) {
+        return 1.0 - sqrt(max(1.0 - t * t, 0.0));
+    }
+    return sqrt(max(1.0 - t * t, 0.0));
+}
+
+// Normal of the surface of the glass, with x and y in screen space and z facing the viewer
+fn bevel_normal(position: vec2<f32>, region: ComputedBlurRegion) -> vec3<f32> {
+    let edge_distance = -shape_sdf(position, region);
+    let bevel_width = max(region.bevel_width, 1.0);
+    let x = edge_distance / bevel_width;
+
+    // Slope of the surface, going inwards from the edge
+    let epsilon = 0.01;
+    let slope = (bevel_height(x + epsilon, region.bevel_profile) - bevel_height(x - epsilon, region.bevel_profile))
+        / (2.0 * epsilon) * region.thickness / bevel_width;
+    let gradient = -shape_normal(position, region) * slope;
+    return normalize(vec3(-gradient, 1.0));
+}
+
+// Refracts a ray looking straight down through the surface of the glass, and returns how far it
+// lands from the pixel on the backdrop, in pixels.
+fn refraction_offset(normal: vec3<f32>, region: ComputedBlurRegion) -> vec2<f32> {
+    let refracted = refract(vec3(0.0, 0.0, -1.0), normal, 1.0 / max(region.ior, 1.0));
+    return refracted.xy / max(-refracted.z, 0.05) * region.thickness;
+}
+
+// Reflects the environment map off the glass, weighted by Schlick's Fresnel approximation
+fn environment_reflection(normal: vec3<f32>, region: ComputedBlurRegion) -> vec3<f32> {
+#ifdef VERTICAL_PASS
+    let f0 = pow((region.ior - 1.0) / (region.ior + 1.0), 2.0);
+    let fresnel = f0 + (1.0 - f0) * pow(1.0 - normal.z, 5.0);
+
+    // Screen space y points down, view space y points up
+    let view_normal = vec3(normal.x, -normal.y, normal.z);
+    let reflected = reflect(vec3(0.0, 0.0, -1.0), view_normal);
+    let direction = (settings.environment_transform * vec4(reflected, 0.0)).xyz;
+
+    // Cube maps are left-handed so the z coordinate is negated
+    let environment = textureSampleLevel(environment_map, environment_sampler, direction * vec3(1.0, 1.0, -1.0), 0.0).rgb;
+    return environment * settings.environment_intensity * region.reflectivity * fresnel;
+#else
+    return vec3(0.0);
+#endif
+}
+
+// Coverage of the drop shadow of a region at a pixel, already multiplied by the shadow's alpha
+fn drop_shadow(position: vec2<f32>, region: ComputedBlurRegion) -> f32 {
+    if (region.drop_shadow_color.a == 0.0) {
+        return 0.0;
+    }
+    // Follows the outline of the glass, so a group casts one shadow and holes cast none
+    let dist = shape_sdf(position - region.drop_shadow_offset, region) - region.drop_shadow_spread;
+    let softness = max(region.drop_shadow_blur, 0.5);
+    return region.drop_shadow_color.a * (1.0 - smoothstep(-softness, softness, dist));
+}
+
+// Runs from 0.0 to 1.0 between the corners of a box furthest behind and ahead along the angle
+fn linear_gradient(p: vec2<f32>, half_size: vec2<f32>, angle: f32) -> f32 {
+    let direction = vec2(cos(angle), sin(angle));
+    let extent = dot(abs(direction), half_size);
+    return clamp(0.5 + dot(p, direction) / (2.0 * max(extent, 1.0)), 0.0, 1.0);
+}
+
+// Runs from 0.0 at the center to 1.0 at the corner of a box furthest from it
+fn radial_gradient(p: vec2<f32>, half_size: vec2<f32>, center: vec2<f32>) -> f32 {
+    let c = (center * 2.0 - 1.0) * half_size;
+    let furthest = abs(c) + half_size;
+    return clamp(length(p - c) / max(length(furthest), 1.0), 0.0, 1.0);
+}
+
+// The strength of the glass from the mask of a region, where `p` is relative to its center
+fn gradient_mask(p: vec2<f32>, half_size: vec2<f32>, region: ComputedBlurRegion) -> f32 {
+    var t: f32;
+    if (region.mask_kind == MASK_LINEAR) {
+        t = linear_gradient(p, half_size, region.mask_direction.x);
+    } else if (region.mask_kind == MASK_RADIAL) {
+        t = radial_gradient(p, half_size, region.mask_direction);
+    } else {
+        return 1.0;
+    }
+    let span = region.mask_end.x - region.mask_start.x;
+    let along = select(step(region.mask_start.x, t), clamp((t - region.mask_start.x) / span, 0.0, 1.0), abs(span) > 0.0001);
+    return mix(region.mask_start.y, region.mask_end.y, along);
+}
+
+// The circle of confusion of a region at a pixel. Both passes use it, so a progressive blur stays
+// separable without seams between them.
+fn region_circle_of_confusion(position: vec2<f32>, region: ComputedBlurRegion) -> f32 {
+    var coc = settings.circle_of_confusion;
+    if (region.progressive_blur != 0u) {
+        let center = vec2((region.max_x + region.min_x) * 0.5, (region.max_y + region.min_y) * 0.5);
+        let half_size = vec2(region.max_x - region.min_x, region.max_y - region.min_y) * 0.5;
+        let t = linear_gradient(position - center, half_size, region.progressive_blur_angle);
+        coc = max(mix(region.progressive_blur_start, region.progressive_blur_end, t), 0.0);
+    }
+    return coc;
+}
+
+// How far the ripples and the wobble of a region move the backdrop at a pixel, in pixels
+fn liquid_offset(position: vec2<f32>, region: ComputedBlurRegion) -> vec2<f32> {
+    var offset = vec2(0.0);
+
+    if (region.wobble_amplitude > 0.0) {
+        let phase = (position * region.wobble_frequency + settings.time * region.wobble_speed) * 2.0 * PI;
+        offset += vec2(sin(phase.y), cos(phase.x * 1.3)) * region.wobble_amplitude;
+    }
+
+    if (region.ripple_amplitude > 0.0) {
+        for (var i = 0u; i < settings.ripples_count; i += 1u) {
+            let ripple = settings.ripples[i];
+            let to_pixel = position - ripple.xy;
+            let distance = length(to_pixel);
+            if (distance < 0.001) {
+                continue;
+            }
+
+            // A few rings travelling outwards with the front, fading as the ripple ages
+            let behind_front = distance - ripple.z * region.ripple_speed;
+            let wavelength = 1.0 / max(region.ripple_frequency, 0.0001);
+            let envelope = exp(-abs(behind_front) / wavelength) * max(1.0 - ripple.z / settings.ripple_duration, 0.0);
+            let wave = sin(behind_front * region.ripple_frequency * 2.0 * PI);
+            offset += to_pixel / distance * wave * envelope * region.ripple_amplitude;
+        }
+    }
+
+    return offset;
+}
+
+// Draws the gradient stroke and the inner glow of a region, which both follow its rounded edge
+fn edge_decoration(color: vec3<f32>, position: vec2<f32>, region: ComputedBlurRegion) -> vec3<f32> {
+    let center = vec2((region.max_x + region.min_x) * 0.5, (region.max_y + region.min_y) * 0.5);
+    let half_size = vec2(region.max_x - region.min_x, region.max_y - region.min_y) * 0.5;
+    let p = position - center;
+    let dist = shape_sdf(position, region);
+    var result = color;
+
+    if (region.inner_glow_width > 0.0) {
+        let glow = region.inner_glow_color.a * (1.0 - smoothstep(0.0, region.inner_glow_width, -dist));
+        result += region.inner_glow_color.rgb * highlight(glow);
+    }
+
+    if (region.stroke_width > 0.0) {
+        let t = linear_gradient(p, half_size, region.stroke_angle);
+        let stroke_color = mix(region.stroke_start_color, region.stroke_end_color, t);
+
+        let coverage = smoothstep(-region.stroke_width - 0.5, -region.stroke_width + 0.5, dist) * (1.0 - smoothstep(-0.5, 0.5, dist));
+        result = mix(result, stroke_color.rgb * highlight(1.0), stroke_color.a * coverage);
+    }
+
+    return result;
+}
+
+// Converts an additive highlight tuned for display-referred colors into the space the glass is
+// lit in. In HDR the highlight stops at white, as clipping would stop it after tonemapping,
+// and is then scaled to the scene's brightness.
+fn highlight(value: f32) -> f32 {
+#ifdef HDR_LIGHTING
+    return min(value, 1.0) * settings.highlight_scale;
+#else
+    return value;
+#endif
+}
+
 fn contrastMatrix(contrast: f32) -> mat4x4<f32> {
     let t = (1.0 - contrast) / 2.0;
 
@@ -133,6 +517,45 @@ fn brightnessMatrix(brightness: f32) -> mat4x4<f32> {
     );
 }
 
+// Same luminance weights as the CSS `saturate()` and `hue-rotate()` filters
+fn saturationMatrix(saturation: f32) -> mat4x4<f32> {
+    let r = 0.213 * (1.0 - saturation);
+    let g = 0.715 * (1.0 - saturation);
+    let b = 0.072 * (1.0 - saturation);
+
+    return mat4x4<f32>(
+        vec4<f32>(r + saturation, r, r, 0.0),
+        vec4<f32>(g, g + saturation, g, 0.0),
+        vec4<f32>(b, b, b + saturation, 0.0),
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
+    );
+}
+
+fn hueMatrix(angle: f32) -> mat4x4<f32> {
+    let c = cos(angle);
+    let s = sin(angle);
+
+    return mat4x4<f32>(
+        vec4<f32>(0.213 + c * 0.787 - s * 0.213, 0.213 - c * 0.213 + s * 0.143, 0.213 - c * 0.213 - s * 0.787, 0.0),
+        vec4<f32>(0.715 - c * 0.715 - s * 0.715, 0.715 + c * 0.285 + s * 0.140, 0.715 - c * 0.715 + s * 0.715, 0.0),
+        vec4<f32>(0.072 - c * 0.072 + s * 0.928, 0.072 - c * 0.072 - s * 0.283, 0.072 + c * 0.928 + s * 0.072, 0.0),
+        vec4<f32>(0.0, 0.0, 0.0, 1.0),
+    );
+}
+
+// Applies the saturation, contrast and hue rotation of a region to its blurred backdrop
+fn adjust_backdrop(color: vec3<f32>, region: ComputedBlurRegion) -> vec3<f32> {
+    let adjustment = contrastMatrix(region.contrast) * saturationMatrix(region.saturation) * hueMatrix(region.hue_rotation);
+    return max((adjustment * vec4<f32>(color, 1.0)).xyz, vec3(0.0));
+}
+
+// Interleaved gradient noise, which has a blue noise like distribution and so reads as fine
+// grain rather than blotches. Centered on zero.
+fn grain(frag_coord: vec2<f32>, size: f32) -> f32 {
+    let cell = floor(frag_coord / max(size, 1.0));
+    return fract(52.9829189 * fract(dot(cell, vec2(0.06711056, 0.00583715)))) - 0.5;
+}
+
 // Performs a single direction of the separable Gaussian blur kernel.
 //
 // * `frag_coord` is the screen-space pixel coordinate of the fragment (i.e. the
@@ -160,9 +583,9 @@ fn gaussian_blur(
     // half the CoC. So we multiply by 0.25.
     let sigma = coc * 0.25;
 
-    // 1.5σ is a good, somewhat aggressive default for support—the number of
-    // texels on each side of the center that we process.
-    let support = i32(ceil(sigma * 1.5));
+    // The support is the number of texels on each side of the center that we
+    // process. 1.5σ is a good, somewhat aggressive default.
+    let support = i32(ceil(sigma * settings.support_ratio));
     let uv = frag_coord.xy / vec2<f32>(textureDimensions(texture));
     let offset = frag_offset / vec2<f32>(textureDimensions(texture));
 
@@ -205,44 +628,253 @@ fn gaussian_blur(
     return sum / weight_sum;
 }
 
+// Averages the texels within half the circle of confusion along one direction, two at a time
+// with bilinear filtering like the Gaussian blur.
+fn box_blur(
+    texture: texture_2d<f32>,
+    sampler_in: sampler,
+    frag_coord: vec4<f32>,
+    coc: f32,
+    frag_offset: vec2<f32>) -> vec3<f32> {
+    let support = i32(ceil(coc * 0.5));
+    let uv = frag_coord.xy / vec2<f32>(textureDimensions(texture));
+    let offset = frag_offset / vec2<f32>(textureDimensions(texture));
+
+    var sum = textureSampleLevel(texture, sampler_in, uv, 0.0).rgb;
+    var weight_sum = 1.0;
+    for (var i = 1; i <= support; i += 2) {
+        // The last texel is sampled alone when the support is odd
+        let weight = select(1.0, 2.0, i < support);
+        let uv_offset = offset * (f32(i) + (weight - 1.0) * 0.5);
+
+        sum += (
+            textureSampleLevel(texture, sampler_in, uv + uv_offset, 0.0).rgb +
+            textureSampleLevel(texture, sampler_in, uv - uv_offset, 0.0).rgb
+        ) * weight;
+        weight_sum += weight * 2.0;
+    }
+
+    return sum / weight_sum;
+}
+
+const KAWASE_MAX_ITERATIONS: i32 = 16;
+
+// Kawase iterations folded into one pass: rings of four diagonal bilinear taps at growing
+// distances, reaching further in the vertical pass than in the horizontal one. The rings are at
+// most two texels apart, the span of a bilinear tap, so a wide blur does not split into ghost
+// copies of the backdrop. Cheap, but not as smooth as the other kernels.
+fn kawase_blur(
+    texture: texture_2d<f32>,
+    sampler_in: sampler,
+    frag_coord: vec4<f32>,
+    coc: f32,
+    frag_offset: vec2<f32>) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(texture));
+    let uv = frag_coord.xy * texel;
+    let reach = coc * select(0.25, 0.125, frag_offset.x > 0.0) + 0.5;
+    let iterations = clamp(i32(ceil(reach)), 1, KAWASE_MAX_ITERATIONS);
+    let spacing = min(reach / f32(iterations), 2.0);
+
+    var sum = vec3(0.0);
+    var weight_sum = 0.0;
+    for (var i = 0; i < iterations; i += 1) {
+        // The inner rings weigh more, for a tent falloff instead of a hard edge
+        let weight = f32(iterations - i);
+        let d = texel * spacing * f32(i + 1);
+        sum += (
+            textureSampleLevel(texture, sampler_in, uv + vec2(d.x, d.y), 0.0).rgb +
+            textureSampleLevel(texture, sampler_in, uv + vec2(-d.x, d.y), 0.0).rgb +
+            textureSampleLevel(texture, sampler_in, uv + vec2(d.x, -d.y), 0.0).rgb +
+            textureSampleLevel(texture, sampler_in, uv + vec2(-d.x, -d.y), 0.0).rgb
+        ) * weight;
+        weight_sum += weight * 4.0;
+    }
+
+    return sum / weight_sum;
+}
+
+const BOKEH_SAMPLES: u32 = 48u;
+const GOLDEN_ANGLE: f32 = 2.39996323;
+
+// Gathers a disk the size of the circle of confusion along a golden angle spiral, like a
+// camera lens. A disk is not separable, so the horizontal pass leaves the image as it is.
+fn bokeh_blur(
+    texture: texture_2d<f32>,
+    sampler_in: sampler,
+    frag_coord: vec4<f32>,
+    coc: f32,
+    frag_offset: vec2<f32>) -> vec3<f32> {
+    let texel = 1.0 / vec2<f32>(textureDimensions(texture));
+    let uv = frag_coord.xy * texel;
+    if (frag_offset.x > 0.0) {
+        return textureSampleLevel(texture, sampler_in, uv, 0.0).rgb;
+    }
+
+    var sum = vec3(0.0);
+    for (var i = 0u; i < BOKEH_SAMPLES; i += 1u) {
+        let radius = sqrt((f32(i) + 0.5) / f32(BOKEH_SAMPLES)) * coc * 0.5;
+        let angle = f32(i) * GOLDEN_ANGLE;
+        sum += textureSampleLevel(texture, sampler_in, uv + vec2(cos(angle), sin(angle)) * radius * texel, 0.0).rgb;
+    }
+
+    return sum / f32(BOKEH_SAMPLES);
+}
+
+#ifdef VERTICAL_PASS
+// The normal of the texture of the glass at a pixel, with xy in screen space and z facing the
+// viewer. Glass without a normal map is flat.
+fn normal_map_normal(position: vec2<f32>, region: ComputedBlurRegion) -> vec3<f32> {
+    // Rotate the pixel into the space of the tiles, which are anchored to the region
+    let c = cos(region.normal_map_rotation);
+    let s = sin(region.normal_map_rotation);
+    let p = position - vec2(region.min_x, region.min_y);
+    let uv = vec2(c * p.x + s * p.y, c * p.y - s * p.x) / max(region.normal_map_tile_size, 1.0);
+
+    var texel: vec3<f32>;
+    switch region.normal_map {
+        case 1u: { texel = textureSampleLevel(normal_map_0, normal_map_sampler, uv, 0.0).rgb; }
+        case 2u: { texel = textureSampleLevel(normal_map_1, normal_map_sampler, uv, 0.0).rgb; }
+        case 3u: { texel = textureSampleLevel(normal_map_2, normal_map_sampler, uv, 0.0).rgb; }
+        case 4u: { texel = textureSampleLevel(normal_map_3, normal_map_sampler, uv, 0.0).rgb; }
+        default: { return vec3(0.0, 0.0, 1.0); }
+    }
+
+    // Normal maps have y pointing up, while screen space y points down
+    let tangent = (texel * 2.0 - 1.0) * vec3(1.0, -1.0, 1.0);
+    let screen = vec2(c * tangent.x - s * tangent.y, s * tangent.x + c * tangent.y);
+    return normalize(vec3(screen * region.normal_map_strength, max(tangent.z, 0.001)));
+}
+
+// Where a magnifying lens samples the sharp backdrop for a pixel, in pixels. The backdrop around
+// the center of the lens is enlarged, and squeezed towards its edge by the barrel distortion.
+fn lens_position(position: vec2<f32>, center: vec2<f32>, half_size: vec2<f32>, region: ComputedBlurRegion) -> vec2<f32> {
+    let from_center = position - center;
+    let q = from_center / max(max(half_size.x, half_size.y), 1.0);
+    let distortion = 1.0 + region.mode_params.y * dot(q, q);
+    return center + from_center * distortion / max(region.mode_params.x, 0.01);
+}
+
+// How frosted a region is at a pixel from its frost map, stretched over the region, where 0.0 is
+// clear and 1.0 is the full blur. Regions without a frost map are frosted evenly.
+fn frost(position: vec2<f32>, region: ComputedBlurRegion) -> f32 {
+    let uv = (position - vec2(region.min_x, region.min_y)) / max(vec2(region.max_x - region.min_x, region.max_y - region.min_y), vec2(1.0));
+    switch region.frost_map {
+        case 1u: { return textureSampleLevel(frost_map_0, frost_map_sampler, uv, 0.0).r; }
+        case 2u: { return textureSampleLevel(frost_map_1, frost_map_sampler, uv, 0.0).r; }
+        case 3u: { return textureSampleLevel(frost_map_2, frost_map_sampler, uv, 0.0).r; }
+        case 4u: { return textureSampleLevel(frost_map_3, frost_map_sampler, uv, 0.0).r; }
+        default: { return 1.0; }
+    }
+}
+
+// The blurred backdrop at a pixel of a region that is frosted by `frost_amount`. Scaling the blur
+// per pixel inside the separable passes would streak wherever a frost map changes sharply, so
+// partly frosted pixels sample the mip chain of the backdrop instead, which is blurred the same
+// way in every direction. Towards full frost it fades into the regular blur, so fully frosted
+// areas match glass without a frost map.
+fn frosted_blur(frag_coord: vec4<f32>, coc: f32, frost_amount: f32) -> vec3<f32> {
+    let full_weight = smoothstep(0.5, 1.0, frost_amount);
+    var full = vec3(0.0);
+    if (full_weight > 0.0) {
+        full = blur(h_pass_texture, texture_sampler, frag_coord, coc, vec2(0.0, 1.0));
+    }
+    if (full_weight >= 1.0) {
+        return full;
+    }
+
+    // The chain starts at half resolution, and each level is blurred by about its texel size in
+    // pixels of the level above, so a level matches a Gaussian with a sigma of 2^level pixels
+    let resolution = vec2<f32>(textureDimensions(original_scene_texture));
+    let uv = frag_coord.xy / resolution;
+    let sigma = coc * frost_amount * 0.25;
+    let level = log2(max(sigma, 1.0));
+    let d = exp2(level) / resolution;
+    let chain = (
+        textureSampleLevel(frost_backdrop, frost_backdrop_sampler, uv + vec2(d.x, d.y), level).rgb +
+        textureSampleLevel(frost_backdrop, frost_backdrop_sampler, uv + vec2(-d.x, d.y), level).rgb +
+        textureSampleLevel(frost_backdrop, frost_backdrop_sampler, uv + vec2(d.x, -d.y), level).rgb +
+        textureSampleLevel(frost_backdrop, frost_backdrop_sampler, uv + vec2(-d.x, -d.y), level).rgb
+    ) * 0.25;
+
+    // Below the first level, fade in from the sharp backdrop
+    let sharp = textureSampleLevel(original_scene_texture, texture_sampler, uv, 0.0).rgb;
+    return mix(mix(sharp, chain, clamp(sigma, 0.0, 1.0)), full, full_weight);
+}
+
+const MOSAIC_SAMPLES: u32 = 4u;
+
+// The color of the mosaic block of a region that a pixel falls in. Each block averages a grid of
+// samples of the sharp backdrop, so it does not flicker as the backdrop moves.
+fn mosaic(position: vec2<f32>, region: ComputedBlurRegion) -> vec3<f32> {
+    let block_size = max(region.mode_params.x, 1.0);
+    let origin = vec2(region.min_x, region.min_y);
+    let block_min = origin + floor((position - origin) / block_size) * block_size;
+    let resolution = vec2<f32>(textureDimensions(original_scene_texture));
+
+    var sum = vec3(0.0);
+    for (var y = 0u; y < MOSAIC_SAMPLES; y += 1u) {
+        for (var x = 0u; x < MOSAIC_SAMPLES; x += 1u) {
+            let sample_position = block_min + (vec2(f32(x), f32(y)) + 0.5) / f32(MOSAIC_SAMPLES) * block_size;
+            sum += textureSampleLevel(original_scene_texture, texture_sampler, sample_position / resolution, 0.0).rgb;
+        }
+    }
+    return sum / f32(MOSAIC_SAMPLES * MOSAIC_SAMPLES);
+}
+#endif
+
+// Runs one pass of the blur kernel selected for the camera.
+fn blur(
+    texture: texture_2d<f32>,
+    sampler_in: sampler,
+    frag_coord: vec4<f32>,
+    coc: f32,
+    frag_offset: vec2<f32>) -> vec3<f32> {
+#ifdef KERNEL_BOX
+    return box_blur(texture, sampler_in, frag_coord, coc, frag_offset);
+#else ifdef KERNEL_KAWASE
+    return kawase_blur(texture, sampler_in, frag_coord, coc, frag_offset);
+#else ifdef KERNEL_BOKEH
+    return bokeh_blur(texture, sampler_in, frag_coord, coc, frag_offset);
+#else
+    return gaussian_blur(texture, sampler_in, frag_coord, coc, frag_offset);
+#endif
+}
+
 #ifdef HORIZONTAL_PASS
 @fragment
 fn horizontal(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    if (settings.fullscreen != 0u) {
+        return vec4<f32>(blur(screen_texture, texture_sampler, in.position, settings.circle_of_confusion, vec2(1.0, 0.0)), 1.0);
+    }
+
     let original_color = textureSample(screen_texture, texture_sampler, in.uv);
     var blurred = false;
+    var coc = settings.circle_of_confusion;
 
-    // Checks if we're in any blur region
+    // Checks if we're in any blur region of the current layer
     for (var i = 0u; i < settings.regions_count; i = i + 1u) {
-        let region = blur_regions[i];
-        if (region.opacity == 0.0) {
+        let region = blur_regions[settings.regions_offset + i];
+        if (region.opacity == 0.0 || region.layer != settings.layer) {
             continue; // Skip this region entirely
         }
 
         // Broad-phase AABB check
-        if (in.position.x < region.min_x || in.position.x > region.max_x ||
-            in.position.y < region.min_y || in.position.y > region.max_y) {
+        if (!in_region_bounds(in.position.xy, region)) {
             continue;
         }
 
-        let center_px = vec2((region.max_x + region.min_x) * 0.5, (region.max_y + region.min_y) * 0.5);
-        let half_size_px = vec2(region.max_x - region.min_x, region.max_y - region.min_y) * 0.5;
-
-        let resolution = vec2<f32>(textureDimensions(screen_texture));
-        let p_relative = (in.position.xy - center_px) / resolution.y;
-        let half_size_st = half_size_px / resolution.y;
-        let radii_st = (region.border_radii) / resolution.y;
-
-        // let dist = sd_rounded_box_per_corner(p_relative, half_size_st, radii_st);
-        let dist = sd_rounded_box_per_corner(p_relative, half_size_st, radii_st) + px(BORDER_INSET_PX, resolution);
+        let dist = shape_sdf(in.position.xy, region) + BORDER_INSET_PX;
         if (dist <= 0.0) {
-            blurred = true;
+            // Lenses, mosaics and redactions show the sharp backdrop or none of it, so they need no blur
+            blurred = region.mode == MODE_GLASS || region.mode == MODE_BLUR_ONLY;
+            coc = region_circle_of_confusion(in.position.xy, region);
             break;
         }
     }
 
     if (blurred) {
         // We're in a region, run the horizontal blur
-        let blurred_color = gaussian_blur(screen_texture, texture_sampler, in.position, settings.circle_of_confusion, vec2(1.0, 0.0));
+        let blurred_color = blur(screen_texture, texture_sampler, in.position, coc, vec2(1.0, 0.0));
         return vec4<f32>(blurred_color, 1.0);
     } else {
         // Not in any region, pass through original color
@@ -254,71 +886,130 @@ fn horizontal(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
 #ifdef VERTICAL_PASS
 @fragment
 fn vertical(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    if (settings.fullscreen != 0u) {
+        var color = blur(h_pass_texture, texture_sampler, in.position, settings.circle_of_confusion, vec2(0.0, 1.0));
+        color = mix(color, settings.fullscreen_tint.rgb * highlight(1.0), settings.fullscreen_tint.a);
+        return vec4<f32>(color * (1.0 - settings.fullscreen_dim), 1.0);
+    }
+
     let resolution = vec2<f32>(textureDimensions(original_scene_texture));
     let pixel_coord = in.position.xy;
     var final_color = textureSample(original_scene_texture, texture_sampler, in.uv).rgb;
     var final_alpha = 1.0;
     var processed = false;
 
-    for (var i = 0u; i < MAX_REGIONS; i = i + 1u) {
-        if (i >= settings.regions_count) {
+    // Regions are stored bottom to top, so walk them backwards to find the topmost hit
+    for (var j = 0u; j < MAX_REGIONS; j = j + 1u) {
+        if (j >= settings.regions_count) {
             break;
         }
         if (processed) { break; }
 
-        let region = blur_regions[i];
-        if (region.opacity == 0.0) {
+        let region = blur_regions[settings.regions_offset + settings.regions_count - 1u - j];
+        if (region.opacity == 0.0 || region.layer != settings.layer) {
             continue; // Skip this region entirely
         }
 
         // Broad-phase AABB check
-        if (in.position.x < region.min_x || in.position.x > region.max_x ||
-            in.position.y < region.min_y || in.position.y > region.max_y) {
+        if (!in_region_bounds(in.position.xy, region)) {
             continue;
         }
 
         let center_px = vec2((region.max_x + region.min_x) * 0.5, (region.max_y + region.min_y) * 0.5);
         let half_size_px = vec2(region.max_x - region.min_x, region.max_y - region.min_y) * 0.5;
 
+        // A group is displaced towards the center of all of its regions, so it bends as one
+        let shape = shape_bounds(region);
+        let shape_center_px = (shape.xy + shape.zw) * 0.5;
+
         // Mask calculations
         let st = (pixel_coord - 0.5 * resolution) / resolution.y;
-        let M = (center_px - 0.5 * resolution) / resolution.y;
+        let M = (shape_center_px - 0.5 * resolution) / resolution.y;
         let p_relative = st - M;
-        let half_size_st = half_size_px / resolution.y;
-        let radii_st = (region.border_radii) / resolution.y;
+        let half_size_st = (shape.zw - shape.xy) * 0.5 / resolution.y;
+        let dist_st = shape_sdf(pixel_coord, region) / resolution.y;
 
-        let masks = create_masks(p_relative, half_size_st, radii_st, resolution, region.displacement_falloff_width, region.displacement_falloff_start);
+        let masks = create_masks(dist_st, resolution, region.displacement_falloff_width, region.displacement_falloff_start);
         let shape_mask = masks.x;
 
         if (shape_mask > 0.0) {
             processed = true;
 
-            let bg_color = textureSample(original_scene_texture, texture_sampler, in.uv).rgb;
+            // The shadow is only cast outside the glass, so it shows through its anti-aliased edge
+            let shadow = drop_shadow(in.position.xy, region) * (1.0 - shape_mask);
+            let coc = region_circle_of_confusion(in.position.xy, region);
+            let frost_amount = frost(in.position.xy, region);
+            let bg_color = mix(textureSample(original_scene_texture, texture_sampler, in.uv).rgb, region.drop_shadow_color.rgb, shadow);
             var color: vec3<f32>;
 
-            // ADD THIS IF/ELSE LOGIC
-            if (region.blur_only > 0.5) {
+            if (region.mode == MODE_BLUR_ONLY) {
                 // --- BLUR_ONLY MODE ---
                 // 1. Get blurred color (no distortion)
-                let blurred_color = gaussian_blur(h_pass_texture, texture_sampler, in.position, settings.circle_of_confusion, vec2(0.0, 1.0));
+                let blurred_color = adjust_backdrop(frosted_blur(in.position, coc, frost_amount), region);
                 // 2. Mix it with the background based on the shape's alpha
                 color = mix(bg_color, blurred_color, shape_mask);
+            } else if (region.mode == MODE_PIXELATE) {
+                color = mix(bg_color, adjust_backdrop(mosaic(pixel_coord, region), region), shape_mask);
+            } else if (region.mode == MODE_REDACT) {
+                color = mix(bg_color, region.mode_params.rgb * highlight(1.0), shape_mask * region.mode_params.a);
             } else {
                 let disp_mask = masks.y;
                 let light_mask = masks.z;
 
-                // UV displacement
-                let center_uv = center_px / resolution;
-                let uv_from_center = in.uv - center_uv;
-                let scale_factor = (1.0 - DISPLACEMENT_SCALE) + DISPLACEMENT_SCALE * smoothstep(0.5, 1.0, disp_mask);
-                let uv2 = center_uv + uv_from_center * scale_factor;
+                let normal = shape_normal(pixel_coord, region);
+                var surface_normal = normalize(vec3(normal * (1.0 - disp_mask), 1.0));
+                if (region.thickness > 0.0) {
+                    surface_normal = bevel_normal(pixel_coord, region);
+                }
+
+                // The texture of the glass tilts the surface on top of its shape
+                let detail = normal_map_normal(pixel_coord, region);
+                surface_normal = normalize(vec3(surface_normal.xy + detail.xy, surface_normal.z * detail.z));
+                let detail_offset = detail.xy * NORMAL_MAP_REFRACTION_PX;
 
-                let distorted_position = vec4<f32>(uv2 * resolution, in.position.zw);
+                var blurred_color: vec3<f32>;
+                if (region.mode == MODE_MAGNIFIER) {
+                    // Sample the sharp backdrop through the lens
+                    let lens_half_size = (shape.zw - shape.xy) * 0.5;
+                    let lens = lens_position(pixel_coord, shape_center_px, lens_half_size, region) + liquid_offset(pixel_coord, region) + detail_offset;
+                    blurred_color = textureSampleLevel(original_scene_texture, texture_sampler, lens / resolution, 0.0).rgb;
+                    if (region.dispersion > 0.0) {
+                        let displacement = lens - pixel_coord;
+                        let red_uv = (pixel_coord + displacement * (1.0 - region.dispersion)) / resolution;
+                        let blue_uv = (pixel_coord + displacement * (1.0 + region.dispersion)) / resolution;
+                        blurred_color.r = textureSampleLevel(original_scene_texture, texture_sampler, red_uv, 0.0).r;
+                        blurred_color.b = textureSampleLevel(original_scene_texture, texture_sampler, blue_uv, 0.0).b;
+                    }
+                } else {
+                    // UV displacement
+                    let center_uv = shape_center_px / resolution;
+                    let uv_from_center = in.uv - center_uv;
+                    let scale_factor = (1.0 - DISPLACEMENT_SCALE) + DISPLACEMENT_SCALE * smoothstep(0.5, 1.0, disp_mask);
+                    var uv2 = center_uv + uv_from_center * scale_factor;
+                    if (region.thickness > 0.0) {
+                        // Refracting through the textured surface already includes the normal map
+                        uv2 = in.uv + refraction_offset(surface_normal, region) / resolution;
+                    } else {
+                        uv2 += detail_offset / resolution;
+                    }
+                    uv2 += liquid_offset(in.position.xy, region) / resolution;
 
-                // Mix base color
-                let blurred_color = gaussian_blur(h_pass_texture, texture_sampler, distorted_position, settings.circle_of_confusion, vec2(0.0, 1.0));
+                    let distorted_position = vec4<f32>(uv2 * resolution, in.position.zw);
+
+                    // Mix base color
+                    blurred_color = frosted_blur(distorted_position, coc, frost_amount);
+                    if (region.dispersion > 0.0) {
+                        // Red bends less and blue more along the same displacement
+                        let displacement = (uv2 - in.uv) * resolution;
+                        let red_position = vec4<f32>(in.position.xy + displacement * (1.0 - region.dispersion), in.position.zw);
+                        let blue_position = vec4<f32>(in.position.xy + displacement * (1.0 + region.dispersion), in.position.zw);
+                        blurred_color.r = frosted_blur(red_position, coc, frost_amount).r;
+                        blurred_color.b = frosted_blur(blue_position, coc, frost_amount).b;
+                    }
+                }
+                blurred_color = adjust_backdrop(blurred_color, region);
                 color = mix(bg_color, blurred_color, shape_mask);
-                color = (brightnessMatrix(region.extra_brightness) * vec4<f32>(color, 1.0)).xyz;
+                color = (brightnessMatrix(highlight(region.extra_brightness)) * vec4<f32>(color, 1.0)).xyz;
                 color = mix(color, vec3(0.0, 0.0, 0.0), region.black_opacity);
 
                 // Apply brightness
@@ -327,19 +1018,32 @@ fn vertical(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
                 let highlight_boost = light_mask * region.light_intensity;
                 
                 // Calculate both lighting styles
-                let additive_result = color + vec3(highlight_boost);
-                let multiplicative_result = color * (1.0 + highlight_boost);
+                let additive_result = color + vec3(highlight(highlight_boost));
+                var multiplicative_result = color * (1.0 + highlight_boost);
+#ifdef HDR_LIGHTING
+                // Brightening past white is clipped in LDR, so stop it there instead of blowing out
+                multiplicative_result = min(multiplicative_result, max(color, vec3(settings.highlight_scale)));
+#endif
 
                 // Blend between the two styles
                 color = mix(additive_result, multiplicative_result, LIGHT_ADAPTIVITY);
 
                 let shadow_p = p_relative + vec2(0.0, px(SHADOW_DISTANCE_PX, resolution));
-                let shadow_dist = sd_box_sharp(shadow_p, half_size_st);
+                var shadow_dist = sd_box_sharp(shadow_p, half_size_st);
+                if (region.group != 0u) {
+                    shadow_dist = shape_sdf(pixel_coord + vec2(0.0, SHADOW_DISTANCE_PX), region) / resolution.y;
+                }
                 color *= 1.0 - region.shadow_intensity * smoothstep(px(80.0, resolution), 0.0, shadow_dist);
 
-                let normal = get_normal(p_relative, half_size_st, radii_st);
-                let light_dir = normalize(LIGHT_SOURCE_POS - in.uv);
-                let NdotL = max(0.0, dot(normal, light_dir));
+                var light_source = LIGHT_SOURCE_POS;
+                var light_attenuation = 1.0;
+                if (settings.point_light != 0u) {
+                    light_source = settings.light_position / resolution;
+                    let light_distance = distance(settings.light_position, in.position.xy) / max(settings.point_light_radius, 1.0);
+                    light_attenuation = settings.point_light_intensity / (1.0 + light_distance * light_distance);
+                }
+                let light_dir = normalize(light_source - in.uv);
+                let NdotL = max(0.0, dot(normalize(normal + detail.xy), light_dir));
 
                 // Sharp, direct specular highlight
                 let specular_highlight = pow(NdotL, region.reflection_shininess) * region.specular_intensity;
@@ -350,15 +1054,50 @@ fn vertical(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
                 let rim_effect = pow(max(0.0, 1.0 - NdotL), region.rim_tightness) * region.rim_intensity;
 
                 // Combine both lighting effects and mask them to the border area
-                let total_reflection = (specular_highlight + rim_effect) * light_mask;
-                color += vec3(total_reflection);
+                var total_reflection = (specular_highlight + rim_effect) * light_mask * light_attenuation;
+
+                // The texture of the glass catches the light across all of it, not just at the rim
+                let sheen = max(dot(detail.xy, light_dir), 0.0) * region.specular_intensity * NORMAL_MAP_SHEEN;
+                total_reflection += sheen * shape_mask * light_attenuation;
+#ifdef HDR_LIGHTING
+                // Past the scene's white the highlights are picked up by bloom
+                color += vec3(highlight(total_reflection) * region.emissive_intensity);
+#else
+                color += vec3(highlight(total_reflection));
+#endif
+
+                if (region.reflectivity > 0.0 && settings.environment_intensity > 0.0) {
+                    color += environment_reflection(surface_normal, region);
+                }
+            }
+
+            if (region.grain_intensity > 0.0) {
+                color += vec3(highlight(grain(in.position.xy, region.grain_size) * region.grain_intensity)) * shape_mask;
+                color = max(color, vec3(0.0));
             }
 
-            final_color = mix(bg_color, color, region.opacity);
+            color = edge_decoration(color, in.position.xy, region);
+
+            let strength = gradient_mask(in.position.xy - center_px, half_size_px, region);
+            final_color = mix(bg_color, color, region.opacity * strength);
             final_alpha = 1.0;
         }
     }
 
+    // Outside the glass, cast the drop shadows of the layer onto the backdrop from the bottom up
+    if (!processed) {
+        for (var i = 0u; i < settings.regions_count; i = i + 1u) {
+            let region = blur_regions[settings.regions_offset + i];
+            if (region.opacity == 0.0 || region.layer != settings.layer) {
+                continue;
+            }
+            if (region.group != 0u && !is_group_leader(i, region)) {
+                continue;
+            }
+            final_color = mix(final_color, region.drop_shadow_color.rgb, drop_shadow(in.position.xy, region));
+        }
+    }
+
     return vec4<f32>(final_color, final_alpha);
 }
 #endif // VERTICAL_PASS
\ No newline at end of file
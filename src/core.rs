@@ -1,6 +1,15 @@
+use bevy::asset::RenderAssetUsages;
+use bevy::ecs::query::QueryItem;
+use bevy::camera::NormalizedRenderTarget;
 use bevy::prelude::*;
 use bevy::render::extract_component::ExtractComponent;
+use bevy::render::render_resource::Extent3d;
 use bevy::render::render_resource::ShaderType;
+use bevy::render::render_resource::TextureDimension;
+use bevy::render::render_resource::TextureFormat;
+use bevy::window::PrimaryWindow;
+
+use crate::shader::BlurRegionsNodePlacement;
 
 pub const DEFAULT_MAX_BLUR_REGIONS_COUNT: usize = 20;
 
@@ -25,7 +34,91 @@ pub struct BlurRegionSettings {
     pub specular_intensity: f32,
     pub reflection_shininess: f32,
     pub opacity: f32,
-    pub blur_only: f32,
+    /// How the region draws its backdrop.
+    pub mode: BlurRegionMode,
+    /// Multiplies the specular and rim highlights with [`BlurRegionsLightingMode::Hdr`], letting them
+    /// go past the scene's white so that `Bloom` makes the rim glow. Has no effect in LDR.
+    pub emissive_intensity: f32,
+    /// Saturation of the blurred backdrop, where 0.0 is grayscale and 1.0 leaves it unchanged.
+    pub saturation: f32,
+    /// Contrast of the blurred backdrop around mid gray, where 1.0 leaves it unchanged.
+    pub contrast: f32,
+    /// Rotates the hue of the blurred backdrop, in radians.
+    pub hue_rotation: f32,
+    /// Strength of the grain added on top of the glass to hide banding in the blur, 0.0 disables it.
+    pub grain_intensity: f32,
+    /// Size of a grain cell in physical pixels. The grain is fixed in screen space so it does not shimmer.
+    pub grain_size: f32,
+    /// How much the edge displacement is spread between the color channels, giving a prism-like
+    /// fringe. 0.0 displaces all channels together.
+    pub dispersion: f32,
+    /// Thickness of the glass in physical pixels. Above 0.0 the backdrop is refracted through the
+    /// bevel of the glass instead of being scaled towards the center of the region.
+    pub thickness: f32,
+    /// Index of refraction of the glass when `thickness` is set.
+    pub ior: f32,
+    /// Width of the bevel around the edge of the glass in physical pixels, when `thickness` is set.
+    pub bevel_width: f32,
+    /// Shape of the bevel, when `thickness` is set.
+    pub bevel_profile: BlurRegionBevelProfile,
+    /// How strongly the glass reflects the camera's environment map, scaled by a Fresnel term so
+    /// that the reflection is strongest at the edges. 0.0 disables the reflection.
+    pub reflectivity: f32,
+    /// Color of the drop shadow cast on the backdrop around the glass. Transparent disables it.
+    pub drop_shadow_color: Color,
+    /// Offset of the drop shadow from the glass, in physical pixels.
+    pub drop_shadow_offset: Vec2,
+    /// How far the edge of the drop shadow is blurred, in physical pixels.
+    pub drop_shadow_blur: f32,
+    /// How far the drop shadow is grown past the glass before it is blurred, in physical pixels.
+    pub drop_shadow_spread: f32,
+    /// Width of the stroke drawn just inside the edge of the glass, in physical pixels. 0.0 disables it.
+    pub stroke_width: f32,
+    /// Color of the stroke where its gradient starts.
+    pub stroke_start_color: Color,
+    /// Color of the stroke where its gradient ends.
+    pub stroke_end_color: Color,
+    /// Direction the stroke gradient runs in across the glass, in radians clockwise from the
+    /// right. The default runs from the top left corner to the bottom right one.
+    pub stroke_angle: f32,
+    /// Color of the glow added along the inside of the edge of the glass.
+    pub inner_glow_color: Color,
+    /// How far the inner glow reaches into the glass, in physical pixels. 0.0 disables it.
+    pub inner_glow_width: f32,
+    /// Varies the strength of the glass across the region.
+    pub mask: BlurRegionMask,
+    /// Varies how blurry the glass is across the region, instead of using the camera's
+    /// [`BlurRegionsCamera::circle_of_confusion`].
+    pub progressive_blur: Option<BlurRegionProgressiveBlur>,
+    /// How far the ripples started with [`BlurRegionsCamera::trigger_ripple`] displace the
+    /// backdrop of the glass, in physical pixels.
+    pub ripple_amplitude: f32,
+    /// How closely packed the rings of a ripple are, in waves per physical pixel.
+    pub ripple_frequency: f32,
+    /// How fast a ripple spreads out, in physical pixels per second.
+    pub ripple_speed: f32,
+    /// How far the glass wobbles the backdrop all of the time, in physical pixels. 0.0 disables it.
+    pub wobble_amplitude: f32,
+    /// How closely packed the wobble is, in waves per physical pixel.
+    pub wobble_frequency: f32,
+    /// How fast the wobble moves, in waves per second.
+    pub wobble_speed: f32,
+    /// Regions of a camera with the same nonzero group melt together into one piece of glass
+    /// where they come close, with one continuous edge, displacement, rim light and shadow.
+    /// The whole group is drawn where its topmost region is, so it stays above everything it
+    /// overlaps, even regions stacked between its own. 0 keeps the region on its own.
+    pub group: u32,
+    /// How far apart the regions of a group start to melt together, in physical pixels.
+    pub group_blend_radius: f32,
+    /// Shapes cut out of the glass, where the backdrop shows through sharp. The edge, the lighting
+    /// and the drop shadow of the glass follow the cut-outs.
+    pub holes: [BlurRegionHole; MAX_BLUR_REGION_HOLES],
+    /// A tiling normal map giving the glass a texture, like reeded, hammered or rain covered glass.
+    pub normal_map: Option<BlurRegionNormalMap>,
+    /// A grayscale image stretched over the region that scales the blur at each pixel, from clear
+    /// at black to the full blur at white. Create and paint it with [`BlurRegionFrostMapExt`].
+    /// Only the id of the image is kept, so a strong [`Handle`] to it must be kept elsewhere.
+    pub frost_map: Option<AssetId<Image>>,
 }
 impl Default for BlurRegionSettings {
     fn default() -> Self {
@@ -42,7 +135,403 @@ impl Default for BlurRegionSettings {
             specular_intensity: 3.,
             reflection_shininess: 5.,
             opacity: 1.0,
-            blur_only: 0.0,
+            mode: BlurRegionMode::Glass,
+            emissive_intensity: 1.0,
+            saturation: 1.0,
+            contrast: 1.0,
+            hue_rotation: 0.0,
+            grain_intensity: 0.0,
+            grain_size: 1.0,
+            dispersion: 0.0,
+            thickness: 0.0,
+            ior: 1.5,
+            bevel_width: 40.0,
+            bevel_profile: BlurRegionBevelProfile::Circular,
+            reflectivity: 0.0,
+            drop_shadow_color: Color::NONE,
+            drop_shadow_offset: Vec2::ZERO,
+            drop_shadow_blur: 0.0,
+            drop_shadow_spread: 0.0,
+            stroke_width: 0.0,
+            stroke_start_color: Color::srgba(1.0, 1.0, 1.0, 0.6),
+            stroke_end_color: Color::srgba(1.0, 1.0, 1.0, 0.0),
+            stroke_angle: std::f32::consts::FRAC_PI_4,
+            inner_glow_color: Color::srgba(1.0, 1.0, 1.0, 0.2),
+            inner_glow_width: 0.0,
+            mask: BlurRegionMask::None,
+            progressive_blur: None,
+            ripple_amplitude: 6.0,
+            ripple_frequency: 0.04,
+            ripple_speed: 300.0,
+            wobble_amplitude: 0.0,
+            wobble_frequency: 0.01,
+            wobble_speed: 0.5,
+            group: 0,
+            group_blend_radius: 24.0,
+            holes: [BlurRegionHole::None; MAX_BLUR_REGION_HOLES],
+            normal_map: None,
+            frost_map: None,
+        }
+    }
+}
+
+impl BlurRegionSettings {
+    /// Blends between two settings. Settings that cannot be blended, like the bevel profile and
+    /// the mask, switch over halfway.
+    pub fn lerp(&self, other: &Self, t: f32) -> Self {
+        Self {
+            glass_brightness: self.glass_brightness.lerp(other.glass_brightness, t),
+            shadow_intensity: self.shadow_intensity.lerp(other.shadow_intensity, t),
+            rim_intensity: self.rim_intensity.lerp(other.rim_intensity, t),
+            rim_tightness: self.rim_tightness.lerp(other.rim_tightness, t),
+            black_opacity: self.black_opacity.lerp(other.black_opacity, t),
+            extra_brightness: self.extra_brightness.lerp(other.extra_brightness, t),
+            light_intensity: self.light_intensity.lerp(other.light_intensity, t),
+            displacement_falloff_start: self.displacement_falloff_start.lerp(other.displacement_falloff_start, t),
+            displacement_falloff_width: self.displacement_falloff_width.lerp(other.displacement_falloff_width, t),
+            specular_intensity: self.specular_intensity.lerp(other.specular_intensity, t),
+            reflection_shininess: self.reflection_shininess.lerp(other.reflection_shininess, t),
+            opacity: self.opacity.lerp(other.opacity, t),
+            mode: self.mode.lerp(&other.mode, t),
+            emissive_intensity: self.emissive_intensity.lerp(other.emissive_intensity, t),
+            saturation: self.saturation.lerp(other.saturation, t),
+            contrast: self.contrast.lerp(other.contrast, t),
+            hue_rotation: self.hue_rotation.lerp(other.hue_rotation, t),
+            grain_intensity: self.grain_intensity.lerp(other.grain_intensity, t),
+            grain_size: self.grain_size.lerp(other.grain_size, t),
+            dispersion: self.dispersion.lerp(other.dispersion, t),
+            thickness: self.thickness.lerp(other.thickness, t),
+            ior: self.ior.lerp(other.ior, t),
+            bevel_width: self.bevel_width.lerp(other.bevel_width, t),
+            bevel_profile: if t < 0.5 { self.bevel_profile } else { other.bevel_profile },
+            reflectivity: self.reflectivity.lerp(other.reflectivity, t),
+            drop_shadow_color: self.drop_shadow_color.mix(&other.drop_shadow_color, t),
+            drop_shadow_offset: self.drop_shadow_offset.lerp(other.drop_shadow_offset, t),
+            drop_shadow_blur: self.drop_shadow_blur.lerp(other.drop_shadow_blur, t),
+            drop_shadow_spread: self.drop_shadow_spread.lerp(other.drop_shadow_spread, t),
+            stroke_width: self.stroke_width.lerp(other.stroke_width, t),
+            stroke_start_color: self.stroke_start_color.mix(&other.stroke_start_color, t),
+            stroke_end_color: self.stroke_end_color.mix(&other.stroke_end_color, t),
+            stroke_angle: self.stroke_angle.lerp(other.stroke_angle, t),
+            inner_glow_color: self.inner_glow_color.mix(&other.inner_glow_color, t),
+            inner_glow_width: self.inner_glow_width.lerp(other.inner_glow_width, t),
+            mask: if t < 0.5 { self.mask } else { other.mask },
+            progressive_blur: if t < 0.5 { self.progressive_blur } else { other.progressive_blur },
+            ripple_amplitude: self.ripple_amplitude.lerp(other.ripple_amplitude, t),
+            ripple_frequency: self.ripple_frequency.lerp(other.ripple_frequency, t),
+            ripple_speed: self.ripple_speed.lerp(other.ripple_speed, t),
+            wobble_amplitude: self.wobble_amplitude.lerp(other.wobble_amplitude, t),
+            wobble_frequency: self.wobble_frequency.lerp(other.wobble_frequency, t),
+            wobble_speed: self.wobble_speed.lerp(other.wobble_speed, t),
+            group: if t < 0.5 { self.group } else { other.group },
+            group_blend_radius: self.group_blend_radius.lerp(other.group_blend_radius, t),
+            holes: if t < 0.5 { self.holes } else { other.holes },
+            normal_map: if t < 0.5 { self.normal_map } else { other.normal_map },
+            frost_map: if t < 0.5 { self.frost_map } else { other.frost_map },
+        }
+    }
+
+    /// Cuts a hole out of the glass, in the first free slot of [`BlurRegionSettings::holes`].
+    /// Holes past the first [`MAX_BLUR_REGION_HOLES`] are ignored.
+    pub fn with_hole(mut self, hole: BlurRegionHole) -> Self {
+        if let Some(slot) = self.holes.iter_mut().find(|slot| **slot == BlurRegionHole::None) {
+            *slot = hole;
+        }
+        self
+    }
+}
+
+/// The most distinct normal maps used by the regions of a camera at once. Regions using further
+/// normal maps are drawn smooth.
+pub const MAX_BLUR_REGIONS_NORMAL_MAPS: usize = 4;
+
+/// A tiling normal map on the surface of the glass, which bends the backdrop seen through it and
+/// catches the light. The image is read as a tangent space normal map with y pointing up, so it
+/// should be loaded as linear rather than sRGB.
+///
+/// Only the id of the image is kept, so a strong [`Handle`] to it must be kept elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct BlurRegionNormalMap {
+    pub image: AssetId<Image>,
+    /// The size of one tile of the image, in physical pixels.
+    pub tile_size: f32,
+    /// Rotates the tiles clockwise, in radians.
+    pub rotation: f32,
+    /// Scales how steep the normals are.
+    pub strength: f32,
+}
+
+impl BlurRegionNormalMap {
+    pub fn new(image: impl Into<AssetId<Image>>) -> Self {
+        Self {
+            image: image.into(),
+            tile_size: 128.0,
+            rotation: 0.0,
+            strength: 1.0,
+        }
+    }
+}
+
+/// The most distinct frost maps used by the regions of a camera at once. Regions using further
+/// frost maps are blurred evenly.
+pub const MAX_BLUR_REGIONS_FROST_MAPS: usize = 4;
+
+/// Creates and paints the images used as [`BlurRegionSettings::frost_map`]. Painting an image
+/// through `Assets<Image>::get_mut` uploads it again, so the frost can change every frame, e.g.
+/// where the player wipes the glass clear.
+pub trait BlurRegionFrostMapExt {
+    /// A frost map of `size` texels, evenly frosted by `frost` from 0.0, clear, to 1.0.
+    fn new_frost_map(size: UVec2, frost: f32) -> Self;
+
+    /// Paints a soft round dab of `frost` with `radius`, both in texels. Besides the `R8Unorm` maps
+    /// of [`new_frost_map`](Self::new_frost_map), it paints the red channel of any texture format
+    /// that [`Image::set_color_at`] supports, and does nothing on others.
+    fn paint_frost(&mut self, center: Vec2, radius: f32, frost: f32);
+
+    /// Paints a stroke of soft round dabs of `frost` from `start` to `end`, all in texels.
+    fn paint_frost_stroke(&mut self, start: Vec2, end: Vec2, radius: f32, frost: f32);
+}
+
+impl BlurRegionFrostMapExt for Image {
+    fn new_frost_map(size: UVec2, frost: f32) -> Self {
+        Image::new_fill(
+            Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[(frost.clamp(0.0, 1.0) * 255.0).round() as u8],
+            TextureFormat::R8Unorm,
+            RenderAssetUsages::default(),
+        )
+    }
+
+    fn paint_frost(&mut self, center: Vec2, radius: f32, frost: f32) {
+        let size = self.size();
+        let frost = frost.clamp(0.0, 1.0);
+        let min = (center - radius).floor().max(Vec2::ZERO).as_uvec2();
+        let max = (center + radius).ceil().as_uvec2().min(size);
+        for y in min.y..max.y {
+            for x in min.x..max.x {
+                let distance = (vec2(x as f32, y as f32) + 0.5).distance(center);
+                // Full strength inside half the radius, fading out to the edge
+                let t = ((radius - distance) / (radius * 0.5).max(0.5)).clamp(0.0, 1.0);
+                let strength = t * t * (3.0 - 2.0 * t);
+                if self.texture_descriptor.format == TextureFormat::R8Unorm {
+                    let Some(texel) = self.data.as_mut().and_then(|data| data.get_mut((y * size.x + x) as usize)) else {
+                        continue;
+                    };
+                    let value = (*texel as f32 / 255.0).lerp(frost, strength);
+                    *texel = (value * 255.0).round() as u8;
+                } else if let Ok(color) = self.get_color_at(x, y) {
+                    // The frost is read from the red channel, so the other channels are left alone
+                    let mut color = color.to_linear();
+                    color.red = color.red.lerp(frost, strength);
+                    let _ = self.set_color_at(x, y, color.into());
+                }
+            }
+        }
+    }
+
+    fn paint_frost_stroke(&mut self, start: Vec2, end: Vec2, radius: f32, frost: f32) {
+        // Dabs a quarter of the radius apart overlap into a smooth stroke
+        let steps = (start.distance(end) / (radius * 0.25).max(0.5)).ceil().max(1.0) as u32;
+        for step in 0..=steps {
+            self.paint_frost(start.lerp(end, step as f32 / steps as f32), radius, frost);
+        }
+    }
+}
+
+/// The most holes that can be cut out of a single region.
+pub const MAX_BLUR_REGION_HOLES: usize = 4;
+
+/// A shape cut out of the glass of a region. Centers are relative to the center of the region and
+/// sizes are the full width and height, all in physical pixels.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect)]
+pub enum BlurRegionHole {
+    /// An empty slot.
+    #[default]
+    None,
+    /// A sharp cornered rectangle.
+    Rect {
+        center: Vec2,
+        size: Vec2,
+    },
+    /// A rectangle with all corners rounded by `radius`.
+    RoundedRect {
+        center: Vec2,
+        size: Vec2,
+        radius: f32,
+    },
+    /// An ellipse filling `size`, such as a circle when it is square.
+    Ellipse {
+        center: Vec2,
+        size: Vec2,
+    },
+}
+
+impl BlurRegionHole {
+    /// Packs the hole into its shader kind, its center and half size, and its corner radius.
+    fn to_gpu(self) -> (u32, Vec4, f32) {
+        let shape = |center: Vec2, size: Vec2| center.extend(size.x * 0.5).extend(size.y * 0.5);
+        match self {
+            BlurRegionHole::None => (0, Vec4::ZERO, 0.0),
+            BlurRegionHole::Rect { center, size } => (1, shape(center, size), 0.0),
+            BlurRegionHole::RoundedRect { center, size, radius } => (1, shape(center, size), radius),
+            BlurRegionHole::Ellipse { center, size } => (2, shape(center, size), 0.0),
+        }
+    }
+}
+
+/// How a region draws its backdrop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect)]
+pub enum BlurRegionMode {
+    /// Blurred glass, with displacement, edge lighting and shadows.
+    #[default]
+    Glass,
+    /// Only blurs the backdrop, without any of the glass effects.
+    BlurOnly,
+    /// A magnifying lens, showing the sharp backdrop around its center enlarged by
+    /// `magnification` instead of blurring it. The edge lighting and shadows are still drawn.
+    /// A positive `distortion` squeezes the backdrop towards the edge like a fisheye, and a
+    /// negative one pinches it instead.
+    Magnifier { magnification: f32, distortion: f32 },
+    /// A mosaic of blocks of `block_size` physical pixels, each filled with the average of the
+    /// backdrop beneath it. Unlike a blur, it keeps no detail that could be recovered.
+    Pixelate { block_size: f32 },
+    /// A solid fill hiding the backdrop completely.
+    Redact { color: Color },
+}
+
+impl BlurRegionMode {
+    /// Blends the parameters of two modes of the same kind, or switches over halfway between
+    /// different ones.
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        match (*self, *other) {
+            (
+                BlurRegionMode::Magnifier {
+                    magnification,
+                    distortion,
+                },
+                BlurRegionMode::Magnifier {
+                    magnification: other_magnification,
+                    distortion: other_distortion,
+                },
+            ) => BlurRegionMode::Magnifier {
+                magnification: magnification.lerp(other_magnification, t),
+                distortion: distortion.lerp(other_distortion, t),
+            },
+            (
+                BlurRegionMode::Pixelate { block_size },
+                BlurRegionMode::Pixelate {
+                    block_size: other_block_size,
+                },
+            ) => BlurRegionMode::Pixelate {
+                block_size: block_size.lerp(other_block_size, t),
+            },
+            (BlurRegionMode::Redact { color }, BlurRegionMode::Redact { color: other_color }) => {
+                BlurRegionMode::Redact {
+                    color: color.mix(&other_color, t),
+                }
+            }
+            _ => {
+                if t < 0.5 {
+                    *self
+                } else {
+                    *other
+                }
+            }
+        }
+    }
+
+    /// Packs the mode into its shader kind and its parameters.
+    fn to_gpu(self) -> (u32, Vec4) {
+        match self {
+            BlurRegionMode::Glass => (0, Vec4::ZERO),
+            BlurRegionMode::BlurOnly => (1, Vec4::ZERO),
+            BlurRegionMode::Magnifier {
+                magnification,
+                distortion,
+            } => (2, vec4(magnification, distortion, 0.0, 0.0)),
+            BlurRegionMode::Pixelate { block_size } => (3, vec4(block_size, 0.0, 0.0, 0.0)),
+            BlurRegionMode::Redact { color } => (4, color.to_linear().to_vec4()),
+        }
+    }
+}
+
+/// The height profile of the bevel around the edge of refractive glass, going from the edge inwards.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+#[repr(u32)]
+pub enum BlurRegionBevelProfile {
+    /// A quarter circle, which bends light strongly right at the edge.
+    #[default]
+    Circular = 0,
+    /// A squircle, which is flatter than a circle and so keeps the refraction closer to the edge.
+    ConvexSquircle = 1,
+    /// A lip that is raised at the edge and curves down into the glass, bending light outwards.
+    ConcaveLip = 2,
+}
+
+/// A stop of a [`BlurRegionMask`] gradient.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct BlurRegionMaskStop {
+    /// Where the stop is along the gradient, from 0.0 to 1.0.
+    pub position: f32,
+    /// The strength of the glass at the stop.
+    pub opacity: f32,
+}
+
+impl BlurRegionMaskStop {
+    pub fn new(position: f32, opacity: f32) -> Self {
+        Self { position, opacity }
+    }
+}
+
+/// Varies the strength of the glass across a region, on top of [`BlurRegionSettings::opacity`].
+/// The strength is interpolated between the two stops and held past them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect)]
+pub enum BlurRegionMask {
+    /// The glass has the same strength everywhere.
+    #[default]
+    None,
+    /// A gradient along `angle`, in radians clockwise from the right, running from the corner of
+    /// the region furthest behind to the corner furthest ahead.
+    Linear {
+        angle: f32,
+        start: BlurRegionMaskStop,
+        end: BlurRegionMaskStop,
+    },
+    /// A gradient running outwards from `center` to the corner of the region furthest from it.
+    /// The center is relative to the region, where (0.5, 0.5) is its middle.
+    Radial {
+        center: Vec2,
+        start: BlurRegionMaskStop,
+        end: BlurRegionMaskStop,
+    },
+}
+
+/// A blur that changes strength along a direction across a region, like the navigation bars that
+/// blur strongly at one edge and fade to sharp towards the content.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct BlurRegionProgressiveBlur {
+    /// The direction the blur changes in, in radians clockwise from the right. The gradient runs
+    /// from the corner of the region furthest behind to the corner furthest ahead.
+    pub angle: f32,
+    /// The circle of confusion at the start of the gradient, in physical pixels.
+    pub start_circle_of_confusion: f32,
+    /// The circle of confusion at the end of the gradient, in physical pixels.
+    pub end_circle_of_confusion: f32,
+}
+
+impl BlurRegionMask {
+    /// Packs the mask into its shader kind, its angle or center, and its two stops.
+    fn to_gpu(self) -> (u32, Vec2, Vec2, Vec2) {
+        let stop = |stop: BlurRegionMaskStop| vec2(stop.position, stop.opacity);
+        match self {
+            BlurRegionMask::None => (0, Vec2::ZERO, Vec2::ZERO, Vec2::ZERO),
+            BlurRegionMask::Linear { angle, start, end } => (1, vec2(angle, 0.0), stop(start), stop(end)),
+            BlurRegionMask::Radial { center, start, end } => (2, center, stop(start), stop(end)),
         }
     }
 }
@@ -69,42 +558,401 @@ pub struct ComputedBlurRegion {
     pub specular_intensity: f32,
     pub reflection_shininess: f32,
     pub opacity: f32,
-    pub blur_only: f32,
-    // Note: ShaderType derives for WGSL require fields to be 16-byte aligned.
-    // We add padding here to ensure compatibility.
-    _p1: f32,
-    _p2: f32,
+    pub mode: u32,
+    /// The glass layer this region is composited in, assigned during extraction.
+    pub(crate) layer: u32,
+    /// The Bevy UI stack index of the node that created this region, or 0 for immediate regions.
+    pub(crate) stack_index: u32,
+    pub emissive_intensity: f32,
+    pub saturation: f32,
+    pub contrast: f32,
+    pub hue_rotation: f32,
+    pub grain_intensity: f32,
+    pub grain_size: f32,
+    pub dispersion: f32,
+    pub thickness: f32,
+    pub ior: f32,
+    pub bevel_width: f32,
+    pub bevel_profile: u32,
+    pub reflectivity: f32,
+    pub drop_shadow_color: Vec4,
+    pub drop_shadow_offset: Vec2,
+    pub drop_shadow_blur: f32,
+    pub drop_shadow_spread: f32,
+    pub stroke_start_color: Vec4,
+    pub stroke_end_color: Vec4,
+    pub inner_glow_color: Vec4,
+    pub stroke_width: f32,
+    pub stroke_angle: f32,
+    pub inner_glow_width: f32,
+    pub mask_kind: u32,
+    /// The angle of a linear mask in x, or the center of a radial one.
+    pub mask_direction: Vec2,
+    /// The position and opacity of the first stop of the mask.
+    pub mask_start: Vec2,
+    /// The position and opacity of the second stop of the mask.
+    pub mask_end: Vec2,
+    pub progressive_blur: u32,
+    pub progressive_blur_angle: f32,
+    pub progressive_blur_start: f32,
+    pub progressive_blur_end: f32,
+    pub ripple_amplitude: f32,
+    pub ripple_frequency: f32,
+    pub ripple_speed: f32,
+    pub wobble_amplitude: f32,
+    pub wobble_frequency: f32,
+    pub wobble_speed: f32,
+    pub group: u32,
+    pub group_blend_radius: f32,
+    /// The kind and corner radius of each hole, one per component.
+    pub hole_kinds: UVec4,
+    pub hole_radii: Vec4,
+    /// The center of each hole in xy and its half size in zw.
+    pub holes: [Vec4; MAX_BLUR_REGION_HOLES],
+    /// The magnification and distortion of a lens in xy, the block size of a mosaic in x, or the
+    /// color of a redaction.
+    pub mode_params: Vec4,
+    /// One more than the slot of the normal map in the normal maps of the camera, or 0 for none.
+    pub normal_map: u32,
+    pub normal_map_tile_size: f32,
+    pub normal_map_rotation: f32,
+    pub normal_map_strength: f32,
+    /// One more than the slot of the frost map in the frost maps of the camera, or 0 for none.
+    pub frost_map: u32,
+}
+
+impl ComputedBlurRegion {
+    /// The area the region draws to, including its drop shadow, as `(min, max)`.
+    fn bounds(&self) -> (Vec2, Vec2) {
+        // Where two regions of a group meet, the smooth union bulges out by up to a quarter of the blend radius
+        let reach = Vec2::splat(if self.group != 0 { self.group_blend_radius.max(0.0) * 0.25 } else { 0.0 });
+        let (min, max) = (vec2(self.min_x, self.min_y) - reach, vec2(self.max_x, self.max_y) + reach);
+        if self.drop_shadow_color.w == 0.0 {
+            return (min, max);
+        }
+        let extent = Vec2::splat((self.drop_shadow_spread + self.drop_shadow_blur).max(0.0));
+        (
+            min.min(min + self.drop_shadow_offset) - extent,
+            max.max(max + self.drop_shadow_offset) + extent,
+        )
+    }
+
+    fn overlaps(&self, other: &ComputedBlurRegion) -> bool {
+        // Regions of a group are drawn as one piece of glass, so they never need separate layers
+        if self.group != 0 && self.group == other.group {
+            return false;
+        }
+        let (min, max) = self.bounds();
+        let (other_min, other_max) = other.bounds();
+        min.x < other_max.x && other_min.x < max.x && min.y < other_max.y && other_min.y < max.y
+    }
+}
+
+/// One more than the slot of an image in `slots`, adding it if there are fewer than `max_slots`,
+/// or 0 if there is no free slot.
+fn texture_slot(slots: &mut Vec<AssetId<Image>>, image: AssetId<Image>, max_slots: usize) -> u32 {
+    let slot = match slots.iter().position(|slot| *slot == image) {
+        Some(slot) => slot,
+        None if slots.len() < max_slots => {
+            slots.push(image);
+            slots.len() - 1
+        }
+        None => return 0,
+    };
+    slot as u32 + 1
+}
+
+/// The regions placed together at the region at `index`: the region itself, every visible region
+/// of its glass group if it is the topmost one, or none for the other regions of a group. The
+/// shader only melts together the regions of a group within one layer, so a group is placed as one
+/// unit where its topmost region is, which keeps it above everything it overlaps.
+fn placement_unit(regions: &[ComputedBlurRegion], index: usize) -> Vec<usize> {
+    let group = regions[index].group;
+    if group == 0 || regions[index].opacity == 0.0 {
+        return vec![index];
+    }
+    let is_member = |region: &ComputedBlurRegion| region.group == group && region.opacity != 0.0;
+    if regions[index + 1..].iter().any(is_member) {
+        return Vec::new();
+    }
+    (0..=index).filter(|&i| is_member(&regions[i])).collect()
+}
+
+/// Whether any region of `unit` overlaps `other`.
+fn unit_overlaps(regions: &[ComputedBlurRegion], unit: &[usize], other: &ComputedBlurRegion) -> bool {
+    other.opacity != 0.0 && unit.iter().any(|&i| other.overlaps(&regions[i]))
+}
+
+/// Assigns each region to the lowest glass layer that is above every earlier region it overlaps,
+/// clamped to `max_layers`. A glass group is placed as one unit at its topmost region, above every
+/// earlier region that overlaps any of its regions.
+pub(crate) fn assign_layers(regions: &mut [ComputedBlurRegion], max_layers: u32) {
+    let max_layer = max_layers.max(1) - 1;
+    let mut placed = vec![false; regions.len()];
+    for i in 0..regions.len() {
+        if placed[i] {
+            continue;
+        }
+        let unit = placement_unit(regions, i);
+        let layer = (0..regions.len())
+            .filter(|&j| placed[j] && unit_overlaps(regions, &unit, &regions[j]))
+            .map(|j| regions[j].layer + 1)
+            .max()
+            .unwrap_or(0)
+            .min(max_layer);
+        for j in unit {
+            regions[j].layer = layer;
+            placed[j] = true;
+        }
+    }
+}
+
+/// Assigns regions to layers in stack order, starting a new layer whenever a region overlaps one
+/// already in the current layer, or other UI nodes are stacked between it and the previous region.
+/// Unlike [`assign_layers`], the layers never go back down, so each layer can be drawn at a single
+/// point in the UI pass, below the lowest node in it, without missing any UI beneath its glass. A
+/// glass group is placed as one unit at its topmost node, and takes on its stack index.
+#[cfg(feature = "bevy_ui_render")]
+pub(crate) fn assign_stacked_layers(regions: &mut [ComputedBlurRegion], max_layers: u32) {
+    let max_layer = max_layers.max(1) - 1;
+    let mut layer = 0;
+    let mut previous_stack_index = None;
+    let mut placed = vec![false; regions.len()];
+    regions.sort_by_key(|region| region.stack_index);
+    for i in 0..regions.len() {
+        if placed[i] || regions[i].opacity == 0.0 {
+            continue;
+        }
+        let unit = placement_unit(regions, i);
+        let overlaps_layer = (0..regions.len())
+            .any(|j| placed[j] && regions[j].layer == layer && unit_overlaps(regions, &unit, &regions[j]));
+        // The layer is drawn below its lowest node, so it would miss any UI stacked in between
+        let nodes_between = previous_stack_index.is_some_and(|previous| regions[i].stack_index > previous + 1);
+        if layer < max_layer && (overlaps_layer || nodes_between) {
+            layer += 1;
+        }
+        previous_stack_index = Some(regions[i].stack_index);
+        for j in unit {
+            regions[j].layer = layer;
+            regions[j].stack_index = regions[i].stack_index;
+            placed[j] = true;
+        }
+    }
+}
+
+/// How the glass lighting treats the colors of the image it is drawn over.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum BlurRegionsLightingMode {
+    /// Display-referred colors in the 0 to 1 range, as found after tonemapping.
+    #[default]
+    Ldr,
+    /// Linear, scene-referred colors, as found before tonemapping on an HDR camera.
+    /// Additive highlights are limited to white and then scaled by
+    /// [`BlurRegionsCamera::hdr_highlight_luminance`], so they keep their relative strength instead
+    /// of blowing out. Has no effect on cameras without HDR.
+    Hdr,
+}
+
+/// The shape of the blur behind the glass.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Reflect)]
+pub enum BlurRegionsKernel {
+    /// A smooth Gaussian blur.
+    #[default]
+    Gaussian,
+    /// An even average over the circle of confusion, which looks softer and flatter.
+    Box,
+    /// A cheap approximation from rings of samples, with at most 64 samples however large the blur is.
+    Kawase,
+    /// A disk shaped blur like an out of focus camera lens. Takes the most samples.
+    Bokeh,
+}
+
+/// Where the light of the glass highlights comes from.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Reflect)]
+pub enum BlurRegionsLightSource {
+    /// A fixed light above the top left of the view.
+    #[default]
+    Fixed,
+    /// A light at the cursor of the window the camera renders to, so the highlights follow the
+    /// pointer. While the cursor is outside of the window, the fixed light is used.
+    Cursor,
+    /// A light at a point on the view, in physical pixels.
+    Point(Vec2),
+}
+
+/// The cursor position of a camera with [`BlurRegionsLightSource::Cursor`], in physical pixels of
+/// its window.
+#[derive(Component, Default, PartialEq)]
+pub struct BlurRegionsCursorPosition(Option<Vec2>);
+
+/// The most ripples drawn at once on the glass of a camera.
+pub const MAX_BLUR_REGIONS_RIPPLES: usize = 8;
+
+/// A ripple started with [`BlurRegionsCamera::trigger_ripple`].
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct BlurRegionsRipple {
+    /// Where the ripple started, in physical pixels.
+    pub position: Vec2,
+    /// How long ago the ripple started, in seconds.
+    pub age: f32,
+}
+
+/// A blur of the whole view, drawn beneath the glass of the camera. It is cheaper than a region
+/// covering the view, as it skips the region shapes and lighting entirely.
+///
+/// Its [`strength`](Self::strength) moves towards [`target_strength`](Self::target_strength) in
+/// real time, so it keeps animating while virtual time is paused. Set the target to 1.0 to fade
+/// the blur in and back to 0.0 to fade it out.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect)]
+pub struct BlurRegionsFullscreenBlur {
+    /// The circle of confusion at full strength, in physical pixels.
+    pub circle_of_confusion: f32,
+    /// Blended over the blurred view by its alpha at full strength.
+    pub tint: Color,
+    /// How much the blurred view is darkened at full strength, from 0.0 to 1.0.
+    pub dim: f32,
+    /// The current strength of the blur from 0.0, where it is not drawn, to 1.0. Scales the
+    /// circle of confusion, tint and dim.
+    pub strength: f32,
+    /// The strength the blur animates towards.
+    pub target_strength: f32,
+    /// How fast the strength moves towards its target, per second.
+    pub transition_speed: f32,
+}
+
+impl Default for BlurRegionsFullscreenBlur {
+    fn default() -> Self {
+        Self {
+            circle_of_confusion: 50.0,
+            tint: Color::NONE,
+            dim: 0.0,
+            strength: 0.0,
+            target_strength: 0.0,
+            transition_speed: 4.0,
+        }
+    }
+}
+
+impl BlurRegionsFullscreenBlur {
+    pub(crate) fn is_visible(&self) -> bool {
+        self.strength > 0.0
+    }
 }
 
 //pub type DefaultBlurRegionsCamera = BlurRegionsCamera<DEFAULT_MAX_BLUR_REGIONS_COUNT>;
 
 /// Indicates that this camera should render blur regions, as well as providing
 /// settings for the blurring.
-#[derive(Component, Debug, Clone, ExtractComponent)]
+#[derive(Component, Debug, Clone)]
+#[require(BlurRegionsCursorPosition)]
 pub struct BlurRegionsCamera {
     /// The diameter of the circle of confusion around the current pixel that is being blurred.
     /// A larger diameter will make the image appear more blurry.
     /// In physical pixels.
     /// https://en.wikipedia.org/wiki/Circle_of_confusion
     pub circle_of_confusion: f32,
+    /// The kernel used to blur the backdrop of the glass. Each kernel is a separate pipeline.
+    pub kernel: BlurRegionsKernel,
+    /// How many standard deviations the Gaussian kernel reaches out on each side. Higher values
+    /// are smoother but take more samples. Only affects [`BlurRegionsKernel::Gaussian`].
+    pub blur_quality: f32,
+    /// The maximum number of glass layers that are composited on top of each other.
+    /// A region that overlaps an earlier region is drawn in a higher layer, so it blurs the
+    /// already glassed result beneath it. Each layer costs an extra pair of blur passes.
+    /// Regions beyond this limit share the topmost layer, where only the topmost region is shown.
+    pub max_layers: u32,
+    /// Draw the glass inside this camera's Bevy UI pass instead of after tonemapping, so that glass
+    /// blurs the UI nodes beneath it as well as the scene. Each layer is drawn just below the
+    /// lowest UI node in it, so the camera must be the one rendering the UI. Glass with other UI
+    /// nodes stacked between it needs a layer each, so raise `max_layers` for busy UIs.
+    #[cfg(feature = "bevy_ui_render")]
+    pub render_in_ui_pass: bool,
+    /// Set this to [`BlurRegionsLightingMode::Hdr`] when the blur node is placed before
+    /// tonemapping on an HDR camera.
+    pub lighting_mode: BlurRegionsLightingMode,
+    /// The luminance, in nits, of a full strength highlight in HDR lighting mode.
+    /// Like other lights in the scene, it is multiplied by the camera's exposure,
+    /// so the default lands at 1.0 with the default exposure.
+    pub hdr_highlight_luminance: f32,
+    /// A cubemap reflected by glass with [`BlurRegionSettings::reflectivity`] set. The image must
+    /// be viewed with a cube dimension. When this is `None`, the camera's `Skybox` is reflected
+    /// instead, if it has one, at the brightness the skybox is drawn with.
+    pub environment_map: Option<Handle<Image>>,
+    /// Multiplies the colors of the reflected environment.
+    pub environment_intensity: f32,
+    /// Blurs the whole view beneath the glass, for pause menus and modal dialogs.
+    pub fullscreen_blur: BlurRegionsFullscreenBlur,
+    /// How long a ripple lasts before it has faded out, in seconds.
+    pub ripple_duration: f32,
+    /// The ripples spreading across the glass of this camera. Unlike the regions, they are kept
+    /// across frames until they have faded out.
+    pub ripples: Vec<BlurRegionsRipple>,
+    /// Where the light of the specular and rim highlights comes from.
+    pub light_source: BlurRegionsLightSource,
+    /// How far from a cursor or point light the highlights fade to half strength, in physical pixels.
+    pub point_light_radius: f32,
+    /// How much brighter the highlights are right next to a cursor or point light.
+    pub point_light_intensity: f32,
     pub regions: Vec<ComputedBlurRegion>,
+    /// The normal maps used by the regions, in the order of their slots.
+    pub(crate) normal_maps: Vec<AssetId<Image>>,
+    /// The frost maps used by the regions, in the order of their slots.
+    pub(crate) frost_maps: Vec<AssetId<Image>>,
 }
 
 impl Default for BlurRegionsCamera {
     fn default() -> Self {
         Self {
             circle_of_confusion: 50.0, // Or your preferred default blur strength
+            kernel: BlurRegionsKernel::Gaussian,
+            blur_quality: 1.5,
+            max_layers: 4,
+            #[cfg(feature = "bevy_ui_render")]
+            render_in_ui_pass: false,
+            lighting_mode: BlurRegionsLightingMode::Ldr,
+            hdr_highlight_luminance: 1000.0,
+            environment_map: None,
+            environment_intensity: 1.0,
+            fullscreen_blur: BlurRegionsFullscreenBlur::default(),
+            ripple_duration: 1.5,
+            ripples: Vec::new(),
+            light_source: BlurRegionsLightSource::Fixed,
+            point_light_radius: 300.0,
+            point_light_intensity: 2.0,
             regions: Vec::new(),
+            normal_maps: Vec::new(),
+            frost_maps: Vec::new(),
         }
     }
 }
 
 impl BlurRegionsCamera {
+    /// Regions are composited in the order they are added, so later regions are drawn on top of
+    /// earlier ones.
     pub fn blur(&mut self, rect: Rect, settings: BlurRegionSettings) {
         self.rounded_blur(rect, Vec4::ZERO, settings);
     }
 
     pub fn rounded_blur(&mut self, rect: Rect, border_radii: Vec4, settings: BlurRegionSettings) {
+        self.push_region(rect, border_radii, settings, 0);
+    }
+
+    pub(crate) fn push_region(&mut self, rect: Rect, border_radii: Vec4, settings: BlurRegionSettings, stack_index: u32) {
+        let (mask_kind, mask_direction, mask_start, mask_end) = settings.mask.to_gpu();
+        let (mode, mode_params) = settings.mode.to_gpu();
+        let normal_map = settings
+            .normal_map
+            .map_or(0, |normal_map| texture_slot(&mut self.normal_maps, normal_map.image, MAX_BLUR_REGIONS_NORMAL_MAPS));
+        let frost_map = settings
+            .frost_map
+            .map_or(0, |frost_map| texture_slot(&mut self.frost_maps, frost_map, MAX_BLUR_REGIONS_FROST_MAPS));
+        // The kinds and radii of the holes are packed into vectors, as uniform arrays of scalars
+        // would need a 16 byte stride, and the shader's MAX_HOLES has to match too
+        const _: () = assert!(MAX_BLUR_REGION_HOLES == 4);
+        let (mut hole_kinds, mut hole_radii, mut holes) = (UVec4::ZERO, Vec4::ZERO, [Vec4::ZERO; MAX_BLUR_REGION_HOLES]);
+        for (i, hole) in settings.holes.iter().enumerate() {
+            (hole_kinds[i], holes[i], hole_radii[i]) = hole.to_gpu();
+        }
         self.regions.push(ComputedBlurRegion {
             min_x: rect.min.x,
             max_x: rect.max.x,
@@ -131,9 +979,56 @@ impl BlurRegionsCamera {
             specular_intensity: settings.specular_intensity,
             reflection_shininess: settings.reflection_shininess,
             opacity: settings.opacity,
-            blur_only: settings.blur_only,
-            _p1: 0.0,
-            _p2: 0.0,
+            mode,
+            layer: 0,
+            stack_index,
+            emissive_intensity: settings.emissive_intensity,
+            saturation: settings.saturation,
+            contrast: settings.contrast,
+            hue_rotation: settings.hue_rotation,
+            grain_intensity: settings.grain_intensity,
+            grain_size: settings.grain_size,
+            dispersion: settings.dispersion,
+            thickness: settings.thickness,
+            ior: settings.ior,
+            bevel_width: settings.bevel_width,
+            bevel_profile: settings.bevel_profile as u32,
+            reflectivity: settings.reflectivity,
+            drop_shadow_color: settings.drop_shadow_color.to_linear().to_vec4(),
+            drop_shadow_offset: settings.drop_shadow_offset,
+            drop_shadow_blur: settings.drop_shadow_blur,
+            drop_shadow_spread: settings.drop_shadow_spread,
+            stroke_start_color: settings.stroke_start_color.to_linear().to_vec4(),
+            stroke_end_color: settings.stroke_end_color.to_linear().to_vec4(),
+            inner_glow_color: settings.inner_glow_color.to_linear().to_vec4(),
+            stroke_width: settings.stroke_width,
+            stroke_angle: settings.stroke_angle,
+            inner_glow_width: settings.inner_glow_width,
+            mask_kind,
+            mask_direction,
+            mask_start,
+            mask_end,
+            progressive_blur: settings.progressive_blur.is_some() as u32,
+            progressive_blur_angle: settings.progressive_blur.map_or(0.0, |blur| blur.angle),
+            progressive_blur_start: settings.progressive_blur.map_or(0.0, |blur| blur.start_circle_of_confusion),
+            progressive_blur_end: settings.progressive_blur.map_or(0.0, |blur| blur.end_circle_of_confusion),
+            ripple_amplitude: settings.ripple_amplitude,
+            ripple_frequency: settings.ripple_frequency,
+            ripple_speed: settings.ripple_speed,
+            wobble_amplitude: settings.wobble_amplitude,
+            wobble_frequency: settings.wobble_frequency,
+            wobble_speed: settings.wobble_speed,
+            group: settings.group,
+            group_blend_radius: settings.group_blend_radius,
+            hole_kinds,
+            hole_radii,
+            holes,
+            mode_params,
+            normal_map,
+            normal_map_tile_size: settings.normal_map.map_or(0.0, |normal_map| normal_map.tile_size),
+            normal_map_rotation: settings.normal_map.map_or(0.0, |normal_map| normal_map.rotation),
+            normal_map_strength: settings.normal_map.map_or(0.0, |normal_map| normal_map.strength),
+            frost_map,
         });
     }
 
@@ -149,8 +1044,60 @@ impl BlurRegionsCamera {
         }
     }
 
+    /// Starts a ripple spreading out from `position`, in physical pixels, across any glass it
+    /// reaches, e.g. where the user clicked. Only the newest [`MAX_BLUR_REGIONS_RIPPLES`] ripples
+    /// are drawn.
+    pub fn trigger_ripple(&mut self, position: Vec2) {
+        self.ripples.push(BlurRegionsRipple { position, age: 0.0 });
+    }
+
     fn clear(&mut self) {
         self.regions.clear();
+        self.normal_maps.clear();
+        self.frost_maps.clear();
+    }
+
+    /// The number of glass layers the regions were assigned to during extraction.
+    pub(crate) fn layers_count(&self) -> u32 {
+        self.regions.iter().filter(|region| region.opacity != 0.0).map(|region| region.layer + 1).max().unwrap_or(0)
+    }
+
+    /// The lowest stack index of the regions in each layer, from the bottom layer to the top.
+    #[cfg(feature = "bevy_ui_render")]
+    pub(crate) fn layer_stack_indices(&self) -> Vec<u32> {
+        let mut stack_indices = vec![u32::MAX; self.layers_count() as usize];
+        for region in self.regions.iter().filter(|region| region.opacity != 0.0) {
+            let stack_index = &mut stack_indices[region.layer as usize];
+            *stack_index = (*stack_index).min(region.stack_index);
+        }
+        stack_indices
+    }
+}
+
+impl ExtractComponent for BlurRegionsCamera {
+    type QueryData = (&'static Self, &'static BlurRegionsCursorPosition);
+    type QueryFilter = ();
+    type Out = Self;
+
+    fn extract_component((camera, cursor): QueryItem<'_, '_, Self::QueryData>) -> Option<Self::Out> {
+        let mut camera = camera.clone();
+
+        // The render world only sees where the light is
+        if camera.light_source == BlurRegionsLightSource::Cursor {
+            camera.light_source = match cursor.0 {
+                Some(position) => BlurRegionsLightSource::Point(position),
+                None => BlurRegionsLightSource::Fixed,
+            };
+        }
+
+        #[cfg(feature = "bevy_ui_render")]
+        if camera.render_in_ui_pass {
+            assign_stacked_layers(&mut camera.regions, camera.max_layers);
+            return Some(camera);
+        }
+
+        assign_layers(&mut camera.regions, camera.max_layers);
+        Some(camera)
     }
 }
 
@@ -160,11 +1107,66 @@ fn clear_blur_regions(mut blur_region_cameras: Query<&mut BlurRegionsCamera>) {
     }
 }
 
-pub struct BlurRegionsPlugin;
+fn age_ripples(time: Res<Time>, mut blur_region_cameras: Query<&mut BlurRegionsCamera>) {
+    for mut blur_region in &mut blur_region_cameras {
+        if blur_region.ripples.is_empty() {
+            continue;
+        }
+        let duration = blur_region.ripple_duration;
+        blur_region.ripples.retain_mut(|ripple| {
+            ripple.age += time.delta_secs();
+            ripple.age < duration
+        });
+    }
+}
+
+fn track_cursor_lights(
+    mut cameras: Query<(&Camera, &BlurRegionsCamera, &mut BlurRegionsCursorPosition)>,
+    primary_window: Query<Entity, With<PrimaryWindow>>,
+    windows: Query<&Window>,
+) {
+    for (camera, blur_regions, mut cursor) in &mut cameras {
+        if blur_regions.light_source != BlurRegionsLightSource::Cursor {
+            continue;
+        }
+        let window = match camera.target.normalize(primary_window.single().ok()) {
+            Some(NormalizedRenderTarget::Window(window_ref)) => windows.get(window_ref.entity()).ok(),
+            _ => None,
+        };
+        // The glass is lit in the pixels of the render target, like the cursor position
+        let position = window.and_then(|window| window.physical_cursor_position());
+        cursor.set_if_neq(BlurRegionsCursorPosition(position));
+    }
+}
+
+fn animate_fullscreen_blur(time: Res<Time<Real>>, mut blur_region_cameras: Query<&mut BlurRegionsCamera>) {
+    for mut blur_region in &mut blur_region_cameras {
+        let fullscreen_blur = &blur_region.fullscreen_blur;
+        if fullscreen_blur.strength == fullscreen_blur.target_strength {
+            continue;
+        }
+        let step = (fullscreen_blur.transition_speed * time.delta_secs()).max(0.0);
+        let strength = fullscreen_blur.target_strength.clamp(
+            fullscreen_blur.strength - step,
+            fullscreen_blur.strength + step,
+        );
+        blur_region.fullscreen_blur.strength = strength;
+    }
+}
+
+pub struct BlurRegionsPlugin {
+    /// Where the blur node runs in the `Core3d` render graph.
+    pub core_3d_placement: BlurRegionsNodePlacement,
+    /// Where the blur node runs in the `Core2d` render graph.
+    pub core_2d_placement: BlurRegionsNodePlacement,
+}
 
 impl Default for BlurRegionsPlugin {
     fn default() -> Self {
-        BlurRegionsPlugin
+        BlurRegionsPlugin {
+            core_3d_placement: BlurRegionsNodePlacement::core_3d(),
+            core_2d_placement: BlurRegionsNodePlacement::core_2d(),
+        }
     }
 }
 
@@ -172,13 +1174,177 @@ impl Plugin for BlurRegionsPlugin {
     fn build(&self, app: &mut App) {
         app.register_type::<BlurRegion>();
         app.register_type::<BlurRegionSettings>();
-        app.add_systems(PreUpdate, clear_blur_regions)
-            .add_plugins(crate::shader::BlurRegionsShaderPlugin);
+        app.add_systems(PreUpdate, (clear_blur_regions, age_ripples, animate_fullscreen_blur))
+            .add_systems(Last, track_cursor_lights)
+            .add_plugins(crate::shader::BlurRegionsShaderPlugin {
+                core_3d_placement: self.core_3d_placement.clone(),
+                core_2d_placement: self.core_2d_placement.clone(),
+            });
 
         #[cfg(feature = "bevy_ui")]
         app.add_plugins(crate::bevy_ui::BlurRegionsBevyUiPlugin);
 
+        #[cfg(feature = "bevy_ui_render")]
+        app.add_plugins(crate::bevy_ui_render::BlurRegionsUiRenderPlugin);
+
         // #[cfg(feature = "egui")]
         // app.add_plugins(crate::egui::BlurRegionsEguiPlugin);
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn region(min: Vec2, max: Vec2, group: u32) -> ComputedBlurRegion {
+        ComputedBlurRegion {
+            min_x: min.x,
+            max_x: max.x,
+            min_y: min.y,
+            max_y: max.y,
+            opacity: 1.0,
+            group,
+            ..default()
+        }
+    }
+
+    fn layers(regions: &[ComputedBlurRegion]) -> Vec<u32> {
+        regions.iter().map(|region| region.layer).collect()
+    }
+
+    #[test]
+    fn overlapping_regions_stack_in_order() {
+        let mut regions = [
+            region(vec2(0.0, 0.0), vec2(100.0, 100.0), 0),
+            region(vec2(50.0, 50.0), vec2(150.0, 150.0), 0),
+            region(vec2(200.0, 200.0), vec2(300.0, 300.0), 0),
+        ];
+        assign_layers(&mut regions, 4);
+        assert_eq!(layers(&regions), [0, 1, 0]);
+    }
+
+    #[test]
+    fn layers_are_clamped() {
+        let mut regions = [
+            region(vec2(0.0, 0.0), vec2(100.0, 100.0), 0),
+            region(vec2(10.0, 10.0), vec2(110.0, 110.0), 0),
+            region(vec2(20.0, 20.0), vec2(120.0, 120.0), 0),
+        ];
+        assign_layers(&mut regions, 2);
+        assert_eq!(layers(&regions), [0, 1, 1]);
+    }
+
+    #[test]
+    fn groups_share_a_layer() {
+        let mut regions = [
+            region(vec2(0.0, 0.0), vec2(100.0, 100.0), 1),
+            region(vec2(90.0, 0.0), vec2(200.0, 100.0), 1),
+        ];
+        assign_layers(&mut regions, 4);
+        assert_eq!(layers(&regions), [0, 0]);
+    }
+
+    #[test]
+    fn groups_stay_above_what_they_overlap() {
+        // B overlaps A and C overlaps B, so the whole group goes above B with its topmost region C
+        let mut regions = [
+            region(vec2(0.0, 0.0), vec2(100.0, 100.0), 1),
+            region(vec2(50.0, 0.0), vec2(250.0, 100.0), 0),
+            region(vec2(200.0, 0.0), vec2(300.0, 100.0), 1),
+        ];
+        assign_layers(&mut regions, 4);
+        assert_eq!(layers(&regions), [1, 0, 1]);
+    }
+
+    #[test]
+    fn groups_are_placed_above_earlier_overlaps_of_any_member() {
+        // Only the second region of the group overlaps the first region
+        let mut regions = [
+            region(vec2(200.0, 0.0), vec2(300.0, 100.0), 0),
+            region(vec2(0.0, 0.0), vec2(100.0, 100.0), 1),
+            region(vec2(250.0, 0.0), vec2(350.0, 100.0), 1),
+        ];
+        assign_layers(&mut regions, 4);
+        assert_eq!(layers(&regions), [0, 1, 1]);
+    }
+
+    #[cfg(feature = "bevy_ui_render")]
+    mod stacked {
+        use super::*;
+
+        fn stacked_region(min: Vec2, max: Vec2, group: u32, stack_index: u32) -> ComputedBlurRegion {
+            ComputedBlurRegion {
+                stack_index,
+                ..region(min, max, group)
+            }
+        }
+
+        fn stack_indices(regions: &[ComputedBlurRegion]) -> Vec<u32> {
+            let camera = BlurRegionsCamera {
+                regions: regions.to_vec(),
+                ..default()
+            };
+            camera.layer_stack_indices()
+        }
+
+        #[test]
+        fn layers_never_go_back_down() {
+            let mut regions = [
+                stacked_region(vec2(0.0, 0.0), vec2(100.0, 100.0), 0, 0),
+                stacked_region(vec2(50.0, 50.0), vec2(150.0, 150.0), 0, 1),
+                stacked_region(vec2(300.0, 300.0), vec2(400.0, 400.0), 0, 2),
+            ];
+            assign_stacked_layers(&mut regions, 4);
+            assert_eq!(layers(&regions), [0, 1, 1]);
+        }
+
+        #[test]
+        fn ui_between_regions_starts_a_layer() {
+            // A glass sidebar, an opaque panel beside it, and a glass button on top of the panel
+            let mut regions = [
+                stacked_region(vec2(0.0, 0.0), vec2(100.0, 500.0), 0, 0),
+                stacked_region(vec2(250.0, 50.0), vec2(350.0, 100.0), 0, 2),
+            ];
+            assign_stacked_layers(&mut regions, 4);
+            assert_eq!(layers(&regions), [0, 1]);
+
+            // The button is drawn after the panel, so it blurs it
+            assert_eq!(stack_indices(&regions), [0, 2]);
+        }
+
+        #[test]
+        fn adjacent_regions_share_a_layer() {
+            let mut regions = [
+                stacked_region(vec2(0.0, 0.0), vec2(100.0, 100.0), 0, 0),
+                stacked_region(vec2(200.0, 0.0), vec2(300.0, 100.0), 0, 1),
+            ];
+            assign_stacked_layers(&mut regions, 4);
+            assert_eq!(layers(&regions), [0, 0]);
+        }
+
+        #[test]
+        fn groups_are_drawn_at_their_topmost_node() {
+            let mut regions = [
+                stacked_region(vec2(0.0, 0.0), vec2(100.0, 100.0), 1, 0),
+                stacked_region(vec2(50.0, 0.0), vec2(250.0, 100.0), 0, 1),
+                stacked_region(vec2(200.0, 0.0), vec2(300.0, 100.0), 1, 2),
+            ];
+            assign_stacked_layers(&mut regions, 4);
+            assert_eq!(layers(&regions), [1, 0, 1]);
+
+            // The group is drawn after B's node, so it blurs it
+            assert_eq!(stack_indices(&regions), [1, 2]);
+        }
+
+        #[test]
+        fn hidden_group_members_do_not_hold_back_the_group() {
+            let mut regions = [
+                stacked_region(vec2(0.0, 0.0), vec2(100.0, 100.0), 1, 0),
+                stacked_region(vec2(200.0, 0.0), vec2(300.0, 100.0), 1, 1),
+            ];
+            regions[1].opacity = 0.0;
+            assign_stacked_layers(&mut regions, 4);
+            assert_eq!(stack_indices(&regions), [0]);
+        }
+    }
+}
@@ -0,0 +1,21 @@
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+// Builds the mip chain that partly frosted glass samples its blur from, one level at a time.
+// Each level is a blurred copy of the one above it at half the resolution.
+
+@group(0) @binding(0) var source_texture: texture_2d<f32>;
+@group(0) @binding(1) var source_sampler: sampler;
+
+@fragment
+fn downsample(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // Four bilinear taps a texel away from the center cover a 4x4 tent of the source, which keeps
+    // the blocks of the chain from showing when it is sampled between its levels
+    let texel = 1.0 / vec2<f32>(textureDimensions(source_texture));
+    let color = (
+        textureSampleLevel(source_texture, source_sampler, in.uv + vec2(texel.x, texel.y), 0.0).rgb +
+        textureSampleLevel(source_texture, source_sampler, in.uv + vec2(-texel.x, texel.y), 0.0).rgb +
+        textureSampleLevel(source_texture, source_sampler, in.uv + vec2(texel.x, -texel.y), 0.0).rgb +
+        textureSampleLevel(source_texture, source_sampler, in.uv + vec2(-texel.x, -texel.y), 0.0).rgb
+    ) * 0.25;
+    return vec4<f32>(color, 1.0);
+}
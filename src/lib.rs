@@ -1,5 +1,7 @@
 #[cfg(feature = "bevy_ui")]
 mod bevy_ui;
+#[cfg(feature = "bevy_ui_render")]
+mod bevy_ui_render;
 mod core;
 // #[cfg(feature = "egui")]
 // mod egui;
@@ -7,7 +9,9 @@ mod shader;
 
 pub mod prelude {
     pub use super::BlurRegion;
+    pub use super::BlurRegionFrostMapExt;
     pub use super::BlurRegionsCamera;
+    pub use super::BlurRegionsNodePlacement;
     pub use super::BlurRegionsPlugin;
     // pub use super::DefaultBlurRegionsCamera;
 
@@ -18,11 +22,31 @@ pub mod prelude {
 }
 
 pub use core::BlurRegion;
+pub use core::BlurRegionBevelProfile;
+pub use core::BlurRegionFrostMapExt;
+pub use core::BlurRegionHole;
+pub use core::BlurRegionMask;
+pub use core::BlurRegionMaskStop;
+pub use core::BlurRegionMode;
+pub use core::BlurRegionNormalMap;
+pub use core::BlurRegionProgressiveBlur;
 pub use core::BlurRegionSettings;
 pub use core::BlurRegionsCamera;
+pub use core::BlurRegionsFullscreenBlur;
+pub use core::BlurRegionsKernel;
+pub use core::BlurRegionsLightSource;
+pub use core::BlurRegionsLightingMode;
 pub use core::BlurRegionsPlugin;
+pub use core::BlurRegionsRipple;
+pub use core::MAX_BLUR_REGIONS_FROST_MAPS;
+pub use core::MAX_BLUR_REGIONS_NORMAL_MAPS;
+pub use core::MAX_BLUR_REGIONS_RIPPLES;
+pub use core::MAX_BLUR_REGION_HOLES;
 // pub use core::DefaultBlurRegionsCamera;
-// pub use shader::BlurRegionsLabel;
+#[cfg(feature = "bevy_ui")]
+pub use bevy_ui::BlurRegionInteractionSettings;
+pub use shader::BlurRegionsLabel;
+pub use shader::BlurRegionsNodePlacement;
 
 // #[cfg(feature = "egui")]
 // pub use crate::egui::EguiAreaBlurExt;
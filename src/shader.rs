@@ -5,6 +5,7 @@ use bevy::core_pipeline::core_2d::graph::Node2d;
 use bevy::core_pipeline::core_3d::graph::Core3d;
 use bevy::core_pipeline::core_3d::graph::Node3d;
 use bevy::core_pipeline::FullscreenShader;
+use bevy::core_pipeline::Skybox;
 use bevy::ecs::query::QueryItem;
 use bevy::ecs::system::lifetimeless::Read;
 use bevy::prelude::*;
@@ -12,13 +13,17 @@ use bevy::render::extract_component::ComponentUniforms;
 use bevy::render::extract_component::ExtractComponentPlugin;
 use bevy::render::extract_component::UniformComponentPlugin;
 use bevy::render::render_graph::NodeRunError;
+use bevy::render::render_graph::RenderGraph;
 use bevy::render::render_graph::RenderGraphContext;
 use bevy::render::render_graph::RenderGraphExt;
+use bevy::render::render_graph::InternedRenderLabel;
 use bevy::render::render_graph::RenderLabel;
+use bevy::render::render_graph::RenderSubGraph;
 use bevy::render::render_graph::ViewNode;
 use bevy::render::render_graph::ViewNodeRunner;
 use bevy::render::render_resource::binding_types::sampler;
 use bevy::render::render_resource::binding_types::texture_2d;
+use bevy::render::render_resource::binding_types::texture_cube;
 use bevy::render::render_resource::binding_types::uniform_buffer;
 use bevy::render::render_resource::AddressMode;
 use bevy::render::render_resource::BindGroupEntries;
@@ -28,6 +33,8 @@ use bevy::render::render_resource::BlendState;
 use bevy::render::render_resource::CachedRenderPipelineId;
 use bevy::render::render_resource::ColorTargetState;
 use bevy::render::render_resource::ColorWrites;
+use bevy::render::render_resource::Extent3d;
+use bevy::render::render_resource::FilterMode;
 use bevy::render::render_resource::FragmentState;
 use bevy::render::render_resource::MultisampleState;
 use bevy::render::render_resource::Operations;
@@ -35,6 +42,7 @@ use bevy::render::render_resource::PipelineCache;
 use bevy::render::render_resource::PrimitiveState;
 use bevy::render::render_resource::RenderPassColorAttachment;
 use bevy::render::render_resource::RenderPassDescriptor;
+use bevy::render::render_resource::RenderPipeline;
 use bevy::render::render_resource::RenderPipelineDescriptor;
 use bevy::render::render_resource::Sampler;
 use bevy::render::render_resource::SamplerBindingType;
@@ -44,21 +52,32 @@ use bevy::render::render_resource::ShaderType;
 use bevy::render::render_resource::SpecializedRenderPipeline;
 use bevy::render::render_resource::SpecializedRenderPipelines;
 use bevy::render::render_resource::TextureDescriptor;
+use bevy::render::render_resource::TextureDimension;
 use bevy::render::render_resource::TextureFormat;
 use bevy::render::render_resource::TextureSampleType;
 use bevy::render::render_resource::TextureUsages;
+use bevy::render::render_resource::TextureView;
+use bevy::render::render_resource::TextureViewDescriptor;
+use bevy::render::render_resource::TextureViewDimension;
+use bevy::render::camera::ExtractedCamera;
+use bevy::render::render_asset::RenderAssets;
 use bevy::render::renderer::RenderContext;
 use bevy::render::renderer::RenderDevice;
+use bevy::render::texture::FallbackImage;
+use bevy::render::texture::GpuImage;
 use bevy::render::view::ExtractedView;
 use bevy::render::view::ViewTarget;
+use bevy::render::Extract;
+use bevy::render::ExtractSchedule;
 use bevy::render::Render;
 use bevy::render::RenderApp;
 use bevy::render::RenderSet;
+use bevy::render::RenderSystems;
 use bevy::shader::ShaderDefVal;
 
 use bevy::render::render_resource::{
     binding_types::{storage_buffer_read_only},
-    StorageBuffer, UniformBuffer,
+    DynamicUniformBuffer, StorageBuffer,
 };
 use bevy::render::renderer::RenderQueue;
 use crate::core::ComputedBlurRegion;
@@ -66,21 +85,95 @@ use crate::core::ComputedBlurRegion;
 #[derive(ShaderType, Default, Clone)]
 struct GpuBlurRegionsSettings {
     circle_of_confusion: f32,
+    regions_offset: u32,
     regions_count: u32,
+    layer: u32,
+    highlight_scale: f32,
+    environment_intensity: f32,
+    environment_transform: Mat4,
+    fullscreen_tint: Vec4,
+    fullscreen: u32,
+    fullscreen_dim: f32,
+    support_ratio: f32,
+    time: f32,
+    ripple_duration: f32,
+    ripples_count: u32,
+    /// The position of each ripple in xy and its age in z.
+    ripples: [Vec4; MAX_BLUR_REGIONS_RIPPLES],
+    light_position: Vec2,
+    point_light: u32,
+    point_light_radius: f32,
+    point_light_intensity: f32,
 }
 
+/// Settings for every layer of every view, and the regions of all views packed together.
 #[derive(Resource, Default)]
 struct BlurRegionsBuffers {
-    settings: UniformBuffer<GpuBlurRegionsSettings>,
+    settings: DynamicUniformBuffer<GpuBlurRegionsSettings>,
     regions: StorageBuffer<Vec<ComputedBlurRegion>>,
 }
 
+/// The dynamic offsets into [`BlurRegionsBuffers::settings`] for each glass layer of a view,
+/// from the bottom layer to the top.
+#[derive(Component)]
+pub struct ViewBlurRegionsLayers(pub(crate) Vec<u32>);
+
+/// The dynamic offset into [`BlurRegionsBuffers::settings`] of the full-screen blur of a view,
+/// which is drawn beneath its glass layers.
+#[derive(Component)]
+pub struct ViewBlurRegionsFullscreen(pub(crate) u32);
+
+/// The normal maps of the regions of a view, in the order of their slots. Unused slots hold the
+/// fallback image.
+#[derive(Component)]
+pub struct ViewBlurRegionsNormalMaps([TextureView; MAX_BLUR_REGIONS_NORMAL_MAPS]);
+
+/// The frost maps of the regions of a view, in the order of their slots. Unused slots hold the
+/// fallback image.
+#[derive(Component)]
+pub struct ViewBlurRegionsFrostMaps([TextureView; MAX_BLUR_REGIONS_FROST_MAPS]);
+
+/// Marks views with regions using a loaded frost map. Each of their glass layers first builds a
+/// mip chain of its backdrop, which partly frosted glass samples its blur from.
+#[derive(Component)]
+pub struct ViewBlurRegionsFrosted;
+
+/// The number of levels in the mip chain of the backdrop for frost maps, starting at half
+/// resolution. Partly frosted glass that would need a stronger blur uses the last level.
+const FROST_BACKDROP_MIP_LEVELS: u32 = 6;
+
+/// The environment cubemap reflected by the glass of a view.
+#[derive(Component)]
+pub struct ViewBlurRegionsEnvironment {
+    texture_view: TextureView,
+    sampler: Sampler,
+    /// Rotates view space directions into the space of the cubemap.
+    transform: Mat4,
+    /// Scales the cubemap, to match the brightness the skybox is drawn with.
+    brightness: f32,
+}
+
+/// The environment maps of the cameras that are viewed as cubemaps, which are the only ones the
+/// glass can reflect.
+#[derive(Resource, Default)]
+struct ExtractedBlurRegionsCubeMaps(Vec<AssetId<Image>>);
+
 use crate::BlurRegionsCamera;
+use crate::BlurRegionsKernel;
+use crate::BlurRegionsLightSource;
+use crate::MAX_BLUR_REGIONS_FROST_MAPS;
+use crate::MAX_BLUR_REGIONS_NORMAL_MAPS;
+use crate::MAX_BLUR_REGIONS_RIPPLES;
+use crate::BlurRegionsLightingMode;
 
 fn get_shader_handle() -> Handle<Shader> {
     Handle::Uuid(Uuid::from_u128(271147050642476932735403127655134602927), std::marker::PhantomData::default())
 }
 
+fn frost_backdrop_shader_handle() -> Handle<Shader> {
+    Handle::Uuid(Uuid::from_u128(118530611430372069486817300596431052316), std::marker::PhantomData)
+}
+
 fn blur_shader_handle() -> Handle<Shader> {
     Handle::Uuid(Uuid::from_u128(23994640822013354325), std::marker::PhantomData::default())
 }
@@ -88,11 +181,15 @@ fn id_pass_shader_handle() -> Handle<Shader> {
     Handle::Uuid(Uuid::from_u128(30310243611322543265), std::marker::PhantomData::default())
 }
 
-pub struct BlurRegionsShaderPlugin;
+pub struct BlurRegionsShaderPlugin {
+    pub core_3d_placement: BlurRegionsNodePlacement,
+    pub core_2d_placement: BlurRegionsNodePlacement,
+}
 
 impl Plugin for BlurRegionsShaderPlugin {
     fn build(&self, app: &mut App) {
         load_internal_asset!(app, get_shader_handle(), "carroted_glass.wgsl", Shader::from_wgsl);
+        load_internal_asset!(app, frost_backdrop_shader_handle(), "frost_backdrop.wgsl", Shader::from_wgsl);
 
         app.add_plugins(ExtractComponentPlugin::<BlurRegionsCamera>::default());
 
@@ -103,19 +200,20 @@ impl Plugin for BlurRegionsShaderPlugin {
         render_app
         .init_resource::<SpecializedRenderPipelines<BlurRegionsPipeline>>()
         .init_resource::<BlurRegionsBuffers>()
+        .init_resource::<ExtractedBlurRegionsCubeMaps>()
+        .add_systems(ExtractSchedule, extract_blur_regions_cube_maps)
         .add_systems(
             Render,
             (
                 prepare_blur_regions_pipelines.in_set(RenderSet::Prepare),
+                warn_mismatched_lighting_modes.in_set(RenderSystems::Prepare),
+                prepare_blur_regions_environments.in_set(RenderSystems::Prepare),
+                prepare_blur_regions_textures.in_set(RenderSystems::Prepare),
                 prepare_blur_regions_buffers.in_set(RenderSet::PrepareBindGroups),
             ),
         )
             .add_render_graph_node::<ViewNodeRunner<BlurRegionsNode>>(Core3d, BlurRegionsLabel)
-            .add_render_graph_edges(Core3d, (Node3d::Tonemapping, BlurRegionsLabel, Node3d::Smaa))
-            .add_render_graph_edges(Core3d, (BlurRegionsLabel, Node3d::Fxaa))
-            .add_render_graph_node::<ViewNodeRunner<BlurRegionsNode>>(Core2d, BlurRegionsLabel)
-            .add_render_graph_edges(Core2d, (Node2d::Tonemapping, BlurRegionsLabel, Node2d::Smaa))
-            .add_render_graph_edges(Core2d, (BlurRegionsLabel, Node2d::Fxaa));
+            .add_render_graph_node::<ViewNodeRunner<BlurRegionsNode>>(Core2d, BlurRegionsLabel);
     }
 
     fn finish(&self, app: &mut App) {
@@ -123,6 +221,12 @@ impl Plugin for BlurRegionsShaderPlugin {
             return;
         };
 
+        // Edges are added once every plugin is built, so the placement can name nodes from
+        // plugins that were added after this one.
+        let core_3d = self.core_3d_placement.add_edges(render_app, Core3d, Node3d::Tonemapping, Node3d::Bloom);
+        let core_2d = self.core_2d_placement.add_edges(render_app, Core2d, Node2d::Tonemapping, Node2d::Bloom);
+        render_app.insert_resource(BlurRegionsHdrPlacement { core_3d, core_2d });
+
         let render_device = render_app.world().resource::<RenderDevice>().clone();
         let fullscreen_shader = render_app.world().resource::<FullscreenShader>().clone();
         render_app.insert_resource(BlurRegionsPipeline::new(
@@ -137,9 +241,11 @@ fn prepare_blur_regions_pipelines(
     pipeline_cache: Res<PipelineCache>,
     mut pipelines: ResMut<SpecializedRenderPipelines<BlurRegionsPipeline>>,
     pipeline: Res<BlurRegionsPipeline>,
-    views: Query<(Entity, &ExtractedView), With<BlurRegionsCamera>>,
+    views: Query<(Entity, &ExtractedView, &BlurRegionsCamera)>,
 ) {
-    for (entity, view) in &views {
+    for (entity, view, camera) in &views {
+        let hdr_lighting = view.hdr && camera.lighting_mode == BlurRegionsLightingMode::Hdr;
+
         let horizontal_pass = BlurRegionsPass {
             pass_label: "blur_regions_horizontal_pass",
             bind_group_label: "blur_regions_bind_group_horizontal",
@@ -149,6 +255,8 @@ fn prepare_blur_regions_pipelines(
                 BlurRegionsPipelineKey {
                     pass: BlurRegionsPassKey::Horizontal,
                     hdr: view.hdr,
+                    hdr_lighting,
+                    kernel: camera.kernel,
                 },
             ),
         };
@@ -162,107 +270,423 @@ fn prepare_blur_regions_pipelines(
                 BlurRegionsPipelineKey {
                     pass: BlurRegionsPassKey::Vertical,
                     hdr: view.hdr,
+                    hdr_lighting,
+                    kernel: camera.kernel,
                 },
             ),
         };
 
-        commands.entity(entity).insert(BlurRegionsPasses([horizontal_pass, vertical_pass]));
+        let frost_backdrop_pass = BlurRegionsPass {
+            pass_label: "blur_regions_frost_backdrop_pass",
+            bind_group_label: "blur_regions_bind_group_frost_backdrop",
+            pipeline: pipelines.specialize(
+                &pipeline_cache,
+                &pipeline,
+                BlurRegionsPipelineKey {
+                    pass: BlurRegionsPassKey::FrostBackdrop,
+                    hdr: view.hdr,
+                    // Downsampling is the same for every lighting mode and kernel
+                    hdr_lighting: false,
+                    kernel: BlurRegionsKernel::default(),
+                },
+            ),
+        };
+
+        commands.entity(entity).insert(BlurRegionsPasses([horizontal_pass, vertical_pass, frost_backdrop_pass]));
     }
 }
 
+/// Where the blur node runs in a core graph, relative to the nodes that the lighting mode of its
+/// cameras has to match.
+#[derive(Clone, Copy)]
+struct HdrPlacement {
+    /// The glass sees the HDR values of the scene.
+    before_tonemapping: bool,
+    /// Highlights past the scene's white are picked up by bloom, or there is no bloom to miss.
+    before_bloom: bool,
+}
+
+/// Where the blur node runs in each core graph, to catch cameras whose lighting mode does not
+/// match it.
+#[derive(Resource)]
+struct BlurRegionsHdrPlacement {
+    core_3d: HdrPlacement,
+    core_2d: HdrPlacement,
+}
+
+fn warn_mismatched_lighting_modes(
+    placement: Res<BlurRegionsHdrPlacement>,
+    views: Query<(&ExtractedView, &ExtractedCamera, &BlurRegionsCamera)>,
+) {
+    for (view, extracted_camera, camera) in &views {
+        if !view.hdr {
+            continue;
+        }
+        let placement = if extracted_camera.render_graph == Core3d.intern() {
+            placement.core_3d
+        } else if extracted_camera.render_graph == Core2d.intern() {
+            placement.core_2d
+        } else {
+            continue;
+        };
+        // The UI pass runs after tonemapping and bloom wherever the blur node is placed
+        #[cfg(feature = "bevy_ui_render")]
+        let placement = if camera.render_in_ui_pass {
+            HdrPlacement { before_tonemapping: false, before_bloom: false }
+        } else {
+            placement
+        };
+
+        match (camera.lighting_mode, placement.before_tonemapping, placement.before_bloom) {
+            (BlurRegionsLightingMode::Hdr, false, _) => warn_once!(
+                "A BlurRegionsCamera uses BlurRegionsLightingMode::Hdr, but its glass is drawn after tonemapping, \
+                so its highlights are clipped and never reach bloom. Place the blur node before tonemapping, for \
+                example with BlurRegionsNodePlacement::core_3d_before_bloom()."
+            ),
+            (BlurRegionsLightingMode::Hdr, true, false) => warn_once!(
+                "A BlurRegionsCamera uses BlurRegionsLightingMode::Hdr, but its glass is drawn after bloom, so its \
+                emissive highlights never glow. Place the blur node before bloom, for example with \
+                BlurRegionsNodePlacement::core_3d_before_bloom()."
+            ),
+            (BlurRegionsLightingMode::Ldr, true, _) => warn_once!(
+                "A BlurRegionsCamera with HDR draws its glass before tonemapping, but uses \
+                BlurRegionsLightingMode::Ldr, so its highlights blow out. Set its lighting_mode to \
+                BlurRegionsLightingMode::Hdr."
+            ),
+            _ => {}
+        }
+    }
+}
+
+/// The label of the render graph node that draws the blur regions, in both the `Core3d` and
+/// `Core2d` graphs. Use it to order your own nodes relative to the glass.
 #[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
 pub struct BlurRegionsLabel;
 
+/// Where [`BlurRegionsLabel`] is inserted into a core render graph, as the nodes it must run after
+/// and the nodes it must run before.
+///
+/// The default runs on the tonemapped image, before anti-aliasing. To process HDR input, place it
+/// before [`Node3d::Tonemapping`] instead. Such a placement is also kept before [`Node3d::Bloom`]
+/// when the graph has it, unless it orders the node against bloom itself, e.g. to run after a node
+/// of your own that follows bloom, add `.after(Node3d::Bloom)`.
+#[derive(Debug, Clone, Default)]
+pub struct BlurRegionsNodePlacement {
+    pub after: Vec<InternedRenderLabel>,
+    pub before: Vec<InternedRenderLabel>,
+}
+
+impl BlurRegionsNodePlacement {
+    /// Runs the blur node after `label`.
+    pub fn after(mut self, label: impl RenderLabel) -> Self {
+        self.after.push(label.intern());
+        self
+    }
+
+    /// Runs the blur node before `label`.
+    pub fn before(mut self, label: impl RenderLabel) -> Self {
+        self.before.push(label.intern());
+        self
+    }
+
+    /// Whether the blur node runs before any of `labels`.
+    fn runs_before(&self, labels: &[InternedRenderLabel]) -> bool {
+        self.before.iter().any(|label| labels.contains(label))
+    }
+
+    /// After tonemapping and before anti-aliasing in the `Core3d` graph.
+    pub fn core_3d() -> Self {
+        Self::default().after(Node3d::Tonemapping).before(Node3d::Smaa).before(Node3d::Fxaa)
+    }
+
+    /// After tonemapping and before anti-aliasing in the `Core2d` graph.
+    pub fn core_2d() -> Self {
+        Self::default().after(Node2d::Tonemapping).before(Node2d::Smaa).before(Node2d::Fxaa)
+    }
+
+    /// Before bloom in the `Core3d` graph, so that HDR highlights on the glass bloom. Requires the
+    /// bloom plugin to be added.
+    pub fn core_3d_before_bloom() -> Self {
+        Self::default().after(Node3d::StartMainPassPostProcessing).before(Node3d::Bloom)
+    }
+
+    /// Before bloom in the `Core2d` graph, so that HDR highlights on the glass bloom. Requires the
+    /// bloom plugin to be added.
+    pub fn core_2d_before_bloom() -> Self {
+        Self::default().after(Node2d::StartMainPassPostProcessing).before(Node2d::Bloom)
+    }
+
+    fn add_edges(
+        &self,
+        render_app: &mut SubApp,
+        graph: impl RenderSubGraph + Clone,
+        tonemapping: impl RenderLabel,
+        bloom: impl RenderLabel,
+    ) -> HdrPlacement {
+        for &label in &self.after {
+            render_app.add_render_graph_edge(graph.clone(), label, BlurRegionsLabel);
+        }
+        for &label in &self.before {
+            render_app.add_render_graph_edge(graph.clone(), BlurRegionsLabel, label);
+        }
+
+        // Bloom is only ordered against tonemapping, so glass placed before tonemapping could still
+        // run after bloom, and its emissive highlights would never glow. Keep it before bloom too,
+        // unless the placement orders it against bloom itself.
+        let bloom = bloom.intern();
+        let before_tonemapping = self.runs_before(&[tonemapping.intern(), bloom]);
+        let after_bloom = self.after.contains(&bloom);
+        let has_bloom = render_app
+            .world()
+            .resource::<RenderGraph>()
+            .get_sub_graph(graph.clone())
+            .is_some_and(|graph| graph.get_node_state(bloom).is_ok());
+        if before_tonemapping && has_bloom && !after_bloom && !self.before.contains(&bloom) {
+            render_app.add_render_graph_edge(graph, BlurRegionsLabel, bloom);
+        }
+
+        HdrPlacement {
+            before_tonemapping,
+            before_bloom: (before_tonemapping && !after_bloom) || !has_bloom,
+        }
+    }
+}
+
 #[derive(Default)]
 pub struct BlurRegionsNode;
 
 impl ViewNode for BlurRegionsNode {
-    type ViewQuery = (Read<ViewTarget>, Read<BlurRegionsPasses>);
+    type ViewQuery = (
+        Read<ViewTarget>,
+        Read<BlurRegionsCamera>,
+        Read<BlurRegionsPasses>,
+        Read<ViewBlurRegionsLayers>,
+        Option<Read<ViewBlurRegionsFullscreen>>,
+    );
 
     fn run(
         &self,
-        _graph: &mut RenderGraphContext,
+        graph: &mut RenderGraphContext,
         render_context: &mut RenderContext,
-        (view_target, passes): QueryItem<Self::ViewQuery>,
+        (view_target, camera, passes, layers, fullscreen): QueryItem<Self::ViewQuery>,
         world: &World,
     ) -> Result<(), NodeRunError> {
-        let blur_regions_pipeline = world.resource::<BlurRegionsPipeline>();
-        let pipeline_cache = world.resource::<PipelineCache>();
-        let buffers = world.resource::<BlurRegionsBuffers>();
-    
-        if buffers.regions.get().is_empty() { return Ok(()); }
-        let Some(settings_binding) = buffers.settings.binding() else { return Ok(()); };
-        let Some(regions_binding) = buffers.regions.binding() else { return Ok(()); };
-    
-        // --- Pass 1: Horizontal ---
-        let horizontal_pass = &passes.0[0];
-        let Some(horizontal_pipeline) = pipeline_cache.get_render_pipeline(horizontal_pass.pipeline) else { return Ok(()); };
-        let source_texture = view_target.main_texture_view();
-    
-        let main_texture = view_target.main_texture();
-        let intermediate_texture_descriptor = TextureDescriptor {
-            label: Some("blur_regions_intermediate_texture"),
-            size: main_texture.size(),
-            mip_level_count: main_texture.mip_level_count(),
-            sample_count: main_texture.sample_count(),
-            dimension: main_texture.dimension(),
-            format: main_texture.format(),
-            usage: main_texture.usage() | TextureUsages::RENDER_ATTACHMENT,
-            view_formats: &[],
-        };
-        let intermediate_texture = render_context
-            .render_device()
-            .create_texture(&intermediate_texture_descriptor);
-        let intermediate_view = intermediate_texture.create_view(&Default::default());
-        let horizontal_bind_group = render_context.render_device().create_bind_group(
-            horizontal_pass.bind_group_label,
-            &blur_regions_pipeline.horizontal_layout,
-            &BindGroupEntries::sequential((
-                source_texture,
-                &blur_regions_pipeline.sampler,
-                settings_binding.clone(),
-                regions_binding.clone(),
-            )),
-        );
+        // These layers are drawn between the UI nodes by the UI pass instead
+        #[cfg(feature = "bevy_ui_render")]
+        if camera.render_in_ui_pass {
+            return Ok(());
+        }
+        #[cfg(not(feature = "bevy_ui_render"))]
+        let _ = camera;
+
+        if layers.0.is_empty() && fullscreen.is_none() { return Ok(()); }
+        let intermediate_view = create_intermediate_view(render_context, view_target);
+
+        // The full-screen blur goes beneath all of the glass
+        let fullscreen = fullscreen.map(|fullscreen| fullscreen.0);
+
+        // Each layer blurs the output of the layer beneath it, so a region sitting on top of
+        // another region shows the already glassed result through it.
+        for settings_offset in fullscreen.into_iter().chain(layers.0.iter().copied()) {
+            render_blur_regions_layer(
+                render_context,
+                world,
+                view_target,
+                passes,
+                graph.view_entity(),
+                &intermediate_view,
+                settings_offset,
+            );
+        }
     
-        let mut horizontal_render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
-            label: Some(horizontal_pass.pass_label),
-            color_attachments: &[Some(RenderPassColorAttachment {
-                view: &intermediate_view,
-                resolve_target: None,
-                ops: Operations::default(),
-                depth_slice: None,
-            })],
-            depth_stencil_attachment: None,
-            timestamp_writes: None,
-            occlusion_query_set: None,
+        Ok(())
+    }
+}
+
+/// Creates the texture the horizontal pass writes to, matching the view's main texture.
+pub(crate) fn create_intermediate_view(render_context: &RenderContext, view_target: &ViewTarget) -> TextureView {
+    let main_texture = view_target.main_texture();
+    let intermediate_texture_descriptor = TextureDescriptor {
+        label: Some("blur_regions_intermediate_texture"),
+        size: main_texture.size(),
+        mip_level_count: main_texture.mip_level_count(),
+        sample_count: main_texture.sample_count(),
+        dimension: main_texture.dimension(),
+        format: main_texture.format(),
+        usage: main_texture.usage() | TextureUsages::RENDER_ATTACHMENT,
+        view_formats: &[],
+    };
+    let intermediate_texture = render_context
+        .render_device()
+        .create_texture(&intermediate_texture_descriptor);
+    intermediate_texture.create_view(&Default::default())
+}
+
+/// Runs the horizontal and vertical passes for a single glass layer, reading the view's main
+/// texture and writing the glassed result back into it. The textures the glass samples are read
+/// from the components of `view_entity`.
+pub(crate) fn render_blur_regions_layer(
+    render_context: &mut RenderContext,
+    world: &World,
+    view_target: &ViewTarget,
+    passes: &BlurRegionsPasses,
+    view_entity: Entity,
+    intermediate_view: &TextureView,
+    settings_offset: u32,
+) {
+    let blur_regions_pipeline = world.resource::<BlurRegionsPipeline>();
+    let Some(normal_maps) = world.get::<ViewBlurRegionsNormalMaps>(view_entity) else { return; };
+    let Some(frost_maps) = world.get::<ViewBlurRegionsFrostMaps>(view_entity) else { return; };
+    let fallback_cube = &world.resource::<FallbackImage>().cube;
+    let (environment_view, environment_sampler) = world.get::<ViewBlurRegionsEnvironment>(view_entity).map_or(
+        (&fallback_cube.texture_view, &fallback_cube.sampler),
+        |environment| (&environment.texture_view, &environment.sampler),
+    );
+    let pipeline_cache = world.resource::<PipelineCache>();
+    let buffers = world.resource::<BlurRegionsBuffers>();
+
+    let Some(settings_binding) = buffers.settings.binding() else { return; };
+    let Some(regions_binding) = buffers.regions.binding() else { return; };
+
+    let horizontal_pass = &passes.0[0];
+    let vertical_pass = &passes.0[1];
+    let Some(horizontal_pipeline) = pipeline_cache.get_render_pipeline(horizontal_pass.pipeline) else { return; };
+    let Some(vertical_pipeline) = pipeline_cache.get_render_pipeline(vertical_pass.pipeline) else { return; };
+
+    let frost_backdrop_pipeline = if world.get::<ViewBlurRegionsFrosted>(view_entity).is_some() {
+        let Some(pipeline) = pipeline_cache.get_render_pipeline(passes.0[2].pipeline) else { return; };
+        Some(pipeline)
+    } else {
+        None
+    };
+
+    let post_process = view_target.post_process_write();
+    let source_texture = post_process.source;
+    let destination_texture = post_process.destination;
+
+    // --- Pass 0: Mip chain of the backdrop for frost maps ---
+    let frost_backdrop = frost_backdrop_pipeline.map(|pipeline| {
+        render_frost_backdrop(render_context, blur_regions_pipeline, &passes.0[2], pipeline, view_target, source_texture)
+    });
+
+    // --- Pass 1: Horizontal ---
+    let horizontal_bind_group = render_context.render_device().create_bind_group(
+        horizontal_pass.bind_group_label,
+        &blur_regions_pipeline.horizontal_layout,
+        &BindGroupEntries::sequential((
+            source_texture,
+            &blur_regions_pipeline.sampler,
+            settings_binding.clone(),
+            regions_binding.clone(),
+        )),
+    );
+
+    let mut horizontal_render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some(horizontal_pass.pass_label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: intermediate_view,
+            resolve_target: None,
+            ops: Operations::default(),
+            depth_slice: None,
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    horizontal_render_pass.set_render_pipeline(horizontal_pipeline);
+    horizontal_render_pass.set_bind_group(0, &horizontal_bind_group, &[settings_offset]);
+    horizontal_render_pass.draw(0..3, 0..1);
+    drop(horizontal_render_pass);
+
+    // --- Pass 2: Vertical ---
+    let vertical_bind_group = render_context.render_device().create_bind_group(
+        vertical_pass.bind_group_label,
+        &blur_regions_pipeline.vertical_layout,
+        &BindGroupEntries::sequential((
+            intermediate_view,
+            source_texture,
+            &blur_regions_pipeline.sampler,
+            settings_binding.clone(),
+            regions_binding.clone(),
+            environment_view,
+            environment_sampler,
+            &normal_maps.0[0],
+            &normal_maps.0[1],
+            &normal_maps.0[2],
+            &normal_maps.0[3],
+            &blur_regions_pipeline.normal_map_sampler,
+            &frost_maps.0[0],
+            &frost_maps.0[1],
+            &frost_maps.0[2],
+            &frost_maps.0[3],
+            &blur_regions_pipeline.frost_map_sampler,
+            frost_backdrop.as_ref().unwrap_or(&world.resource::<FallbackImage>().d2.texture_view),
+            &blur_regions_pipeline.frost_backdrop_sampler,
+        )),
+    );
+
+    let mut vertical_render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+        label: Some(vertical_pass.pass_label),
+        color_attachments: &[Some(RenderPassColorAttachment {
+            view: destination_texture,
+            resolve_target: None,
+            ops: Operations::default(),
+            depth_slice: None,
+        })],
+        depth_stencil_attachment: None,
+        timestamp_writes: None,
+        occlusion_query_set: None,
+    });
+    vertical_render_pass.set_render_pipeline(vertical_pipeline);
+    vertical_render_pass.set_bind_group(0, &vertical_bind_group, &[settings_offset]);
+    vertical_render_pass.draw(0..3, 0..1);
+}
+
+/// Downsamples `source_texture` into a new mip chain for partly frosted glass, and returns a view
+/// of all of its levels.
+fn render_frost_backdrop(
+    render_context: &mut RenderContext,
+    blur_regions_pipeline: &BlurRegionsPipeline,
+    pass: &BlurRegionsPass,
+    pipeline: &RenderPipeline,
+    view_target: &ViewTarget,
+    source_texture: &TextureView,
+) -> TextureView {
+    let main_texture = view_target.main_texture();
+    let size = Extent3d {
+        width: (main_texture.width() / 2).max(1),
+        height: (main_texture.height() / 2).max(1),
+        depth_or_array_layers: 1,
+    };
+    let texture = render_context.render_device().create_texture(&TextureDescriptor {
+        label: Some("blur_regions_frost_backdrop_texture"),
+        size,
+        mip_level_count: FROST_BACKDROP_MIP_LEVELS.min(size.max_mips(TextureDimension::D2)),
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: main_texture.format(),
+        usage: TextureUsages::RENDER_ATTACHMENT | TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    });
+
+    // Each level is rendered from the one above it, starting from the backdrop itself
+    let mut source = source_texture.clone();
+    for level in 0..texture.mip_level_count() {
+        let destination = texture.create_view(&TextureViewDescriptor {
+            base_mip_level: level,
+            mip_level_count: Some(1),
+            ..default()
         });
-        horizontal_render_pass.set_render_pipeline(horizontal_pipeline);
-        horizontal_render_pass.set_bind_group(0, &horizontal_bind_group, &[]);
-        horizontal_render_pass.draw(0..3, 0..1);
-        drop(horizontal_render_pass);
-    
-        // --- Pass 2: Vertical ---
-        let vertical_pass = &passes.0[1];
-        let Some(vertical_pipeline) = pipeline_cache.get_render_pipeline(vertical_pass.pipeline) else { return Ok(()); };
-        let destination_texture = view_target.post_process_write().destination;
-    
-        let vertical_bind_group = render_context.render_device().create_bind_group(
-            vertical_pass.bind_group_label,
-            &blur_regions_pipeline.vertical_layout,
-            &BindGroupEntries::sequential((
-                &intermediate_view,
-                source_texture,
-                &blur_regions_pipeline.sampler,
-                settings_binding.clone(),
-                regions_binding.clone(),
-            )),
+        let bind_group = render_context.render_device().create_bind_group(
+            pass.bind_group_label,
+            &blur_regions_pipeline.frost_backdrop_layout,
+            &BindGroupEntries::sequential((&source, &blur_regions_pipeline.frost_backdrop_sampler)),
         );
-    
-        let mut vertical_render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
-            label: Some(vertical_pass.pass_label),
+
+        let mut render_pass = render_context.begin_tracked_render_pass(RenderPassDescriptor {
+            label: Some(pass.pass_label),
             color_attachments: &[Some(RenderPassColorAttachment {
-                view: destination_texture,
+                view: &destination,
                 resolve_target: None,
                 ops: Operations::default(),
                 depth_slice: None,
@@ -271,19 +695,29 @@ impl ViewNode for BlurRegionsNode {
             timestamp_writes: None,
             occlusion_query_set: None,
         });
-        vertical_render_pass.set_render_pipeline(vertical_pipeline);
-        vertical_render_pass.set_bind_group(0, &vertical_bind_group, &[]);
-        vertical_render_pass.draw(0..3, 0..1);
-    
-        Ok(())
+        render_pass.set_render_pipeline(pipeline);
+        render_pass.set_bind_group(0, &bind_group, &[]);
+        render_pass.draw(0..3, 0..1);
+        drop(render_pass);
+
+        source = destination;
     }
+
+    texture.create_view(&TextureViewDescriptor::default())
 }
 
 #[derive(Resource)]
 pub struct BlurRegionsPipeline {
     horizontal_layout: BindGroupLayout,
     vertical_layout: BindGroupLayout,
+    frost_backdrop_layout: BindGroupLayout,
     sampler: Sampler,
+    /// Repeats, so the normal maps tile across the glass.
+    normal_map_sampler: Sampler,
+    /// Clamps, so the frost maps are stretched over the glass exactly once.
+    frost_map_sampler: Sampler,
+    /// Filters between the levels of the mip chain of the backdrop for frost maps.
+    frost_backdrop_sampler: Sampler,
     fullscreen_shader: FullscreenShader,
 }
 
@@ -296,7 +730,7 @@ impl BlurRegionsPipeline {
                 (
                     texture_2d(TextureSampleType::Float { filterable: true }),
                     sampler(SamplerBindingType::Filtering),
-                    uniform_buffer::<GpuBlurRegionsSettings>(false),
+                    uniform_buffer::<GpuBlurRegionsSettings>(true),
                     storage_buffer_read_only::<ComputedBlurRegion>(false),
                 ),
             ),
@@ -314,9 +748,42 @@ impl BlurRegionsPipeline {
                     // Binding 2: Sampler
                     sampler(SamplerBindingType::Filtering),
                     // Binding 3: Settings Uniform
-                    uniform_buffer::<GpuBlurRegionsSettings>(false),
+                    uniform_buffer::<GpuBlurRegionsSettings>(true),
                     // Binding 4: Regions Storage
                     storage_buffer_read_only::<ComputedBlurRegion>(false),
+                    // Binding 5: Environment Cubemap
+                    texture_cube(TextureSampleType::Float { filterable: true }),
+                    // Binding 6: Environment Sampler
+                    sampler(SamplerBindingType::Filtering),
+                    // Bindings 7 to 10: Normal Maps
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    // Binding 11: Normal Map Sampler
+                    sampler(SamplerBindingType::Filtering),
+                    // Bindings 12 to 15: Frost Maps
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    // Binding 16: Frost Map Sampler
+                    sampler(SamplerBindingType::Filtering),
+                    // Binding 17: Mip Chain of the Backdrop for Frost Maps
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    // Binding 18: Mip Chain Sampler
+                    sampler(SamplerBindingType::Filtering),
+                ),
+            ),
+        );
+
+        let frost_backdrop_layout = render_device.create_bind_group_layout(
+            "blur_regions_frost_backdrop_layout",
+            &BindGroupLayoutEntries::sequential(
+                ShaderStages::FRAGMENT,
+                (
+                    texture_2d(TextureSampleType::Float { filterable: true }),
+                    sampler(SamplerBindingType::Filtering),
                 ),
             ),
         );
@@ -327,17 +794,46 @@ impl BlurRegionsPipeline {
             ..default()
         });
 
+        let normal_map_sampler = render_device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        let frost_map_sampler = render_device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            ..default()
+        });
+
+        let frost_backdrop_sampler = render_device.create_sampler(&SamplerDescriptor {
+            address_mode_u: AddressMode::ClampToEdge,
+            address_mode_v: AddressMode::ClampToEdge,
+            mag_filter: FilterMode::Linear,
+            min_filter: FilterMode::Linear,
+            mipmap_filter: FilterMode::Linear,
+            ..default()
+        });
+
         Self {
             horizontal_layout,
             vertical_layout,
+            frost_backdrop_layout,
             sampler,
+            normal_map_sampler,
+            frost_map_sampler,
+            frost_backdrop_sampler,
             fullscreen_shader,
         }
     }
 }
 
 #[derive(Component)]
-pub struct BlurRegionsPasses([BlurRegionsPass; 2]);
+pub struct BlurRegionsPasses([BlurRegionsPass; 3]);
 
 pub struct BlurRegionsPass {
     pass_label: &'static str,
@@ -349,46 +845,208 @@ pub struct BlurRegionsPass {
 pub enum BlurRegionsPassKey {
     Horizontal,
     Vertical,
+    FrostBackdrop,
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy)]
 pub struct BlurRegionsPipelineKey {
     pass: BlurRegionsPassKey,
     hdr: bool,
+    hdr_lighting: bool,
+    kernel: BlurRegionsKernel,
 }
 fn prepare_blur_regions_buffers(
+    mut commands: Commands,
     mut buffers: ResMut<BlurRegionsBuffers>,
     render_device: Res<RenderDevice>,
     render_queue: Res<RenderQueue>,
-    cameras: Query<&BlurRegionsCamera>,
+    time: Res<Time>,
+    images: Res<RenderAssets<GpuImage>>,
+    cameras: Query<(
+        Entity,
+        &BlurRegionsCamera,
+        Option<&ExtractedCamera>,
+        Option<&ViewBlurRegionsEnvironment>,
+    )>,
 ) {
-    let Some(camera) = cameras.iter().next() else {
-        // If no camera exists with the component, ensure the buffer is empty.
-        if !buffers.regions.get().is_empty() {
-            buffers.regions.get_mut().clear();
-            buffers.regions.write_buffer(&render_device, &render_queue);
+    buffers.settings.clear();
+    buffers.regions.get_mut().clear();
+
+    for (entity, camera, extracted_camera, environment) in &cameras {
+        let exposure = extracted_camera.map_or(1.0, |extracted_camera| extracted_camera.exposure);
+        let regions_offset = buffers.regions.get().len() as u32;
+
+        let (environment_intensity, environment_transform) = environment.map_or((0.0, Mat4::IDENTITY), |environment| {
+            (camera.environment_intensity * environment.brightness, environment.transform)
+        });
+
+        // Only the newest ripples are drawn
+        let mut ripples = [Vec4::ZERO; MAX_BLUR_REGIONS_RIPPLES];
+        let newest_ripples = camera.ripples.iter().rev().take(MAX_BLUR_REGIONS_RIPPLES);
+        for (gpu_ripple, ripple) in ripples.iter_mut().zip(newest_ripples) {
+            *gpu_ripple = ripple.position.extend(ripple.age).extend(0.0);
         }
-        return;
-    };
+        let layer_settings = GpuBlurRegionsSettings {
+            circle_of_confusion: camera.circle_of_confusion,
+            regions_offset,
+            regions_count: camera.regions.len() as u32,
+            layer: 0,
+            highlight_scale: camera.hdr_highlight_luminance * exposure,
+            environment_intensity,
+            environment_transform,
+            fullscreen_tint: Vec4::ZERO,
+            fullscreen: 0,
+            fullscreen_dim: 0.0,
+            support_ratio: camera.blur_quality,
+            time: time.elapsed_secs_wrapped(),
+            ripple_duration: camera.ripple_duration,
+            ripples_count: camera.ripples.len().min(MAX_BLUR_REGIONS_RIPPLES) as u32,
+            ripples,
+            light_position: match camera.light_source {
+                BlurRegionsLightSource::Point(position) => position,
+                _ => Vec2::ZERO,
+            },
+            point_light: matches!(camera.light_source, BlurRegionsLightSource::Point(_)) as u32,
+            point_light_radius: camera.point_light_radius,
+            point_light_intensity: camera.point_light_intensity,
+        };
 
-    // Write settings to the uniform buffer
-    let settings = GpuBlurRegionsSettings {
-        circle_of_confusion: camera.circle_of_confusion,
-        regions_count: camera.regions.len() as u32,
-    };
-    buffers.settings.set(settings);
-    buffers.settings.write_buffer(&render_device, &render_queue);
+        // Write one settings entry per layer
+        let offsets = (0..camera.layers_count())
+            .map(|layer| buffers.settings.push(&GpuBlurRegionsSettings { layer, ..layer_settings.clone() }))
+            .collect();
+        commands.entity(entity).insert(ViewBlurRegionsLayers(offsets));
+
+        let fullscreen_blur = &camera.fullscreen_blur;
+        if fullscreen_blur.is_visible() {
+            let strength = fullscreen_blur.strength.min(1.0);
+            let tint = fullscreen_blur.tint.to_linear().to_vec4();
+            let offset = buffers.settings.push(&GpuBlurRegionsSettings {
+                circle_of_confusion: fullscreen_blur.circle_of_confusion * strength,
+                fullscreen_tint: tint.truncate().extend(tint.w * strength),
+                fullscreen: 1,
+                fullscreen_dim: fullscreen_blur.dim * strength,
+                ..layer_settings
+            });
+            commands.entity(entity).insert(ViewBlurRegionsFullscreen(offset));
+        } else {
+            commands.entity(entity).remove::<ViewBlurRegionsFullscreen>();
+        }
+
+        // Regions whose normal map has not loaded yet are drawn smooth, and those whose frost map
+        // has not loaded yet are blurred evenly
+        let is_loaded = |slots: &[AssetId<Image>], slot: u32| {
+            let slot = (slot as usize).wrapping_sub(1);
+            slots.get(slot).is_some_and(|image| images.get(*image).is_some())
+        };
+        let regions = camera.regions.iter().cloned().map(|mut region| {
+            if !is_loaded(&camera.normal_maps, region.normal_map) {
+                region.normal_map = 0;
+            }
+            if !is_loaded(&camera.frost_maps, region.frost_map) {
+                region.frost_map = 0;
+            }
+            region
+        });
+        buffers.regions.get_mut().extend(regions);
+    }
 
     // Write all region data to the storage buffer
-    buffers.regions.set(camera.regions.clone());
+    buffers.settings.write_buffer(&render_device, &render_queue);
     buffers.regions.write_buffer(&render_device, &render_queue);
 }
 
+fn extract_blur_regions_cube_maps(
+    mut cube_maps: ResMut<ExtractedBlurRegionsCubeMaps>,
+    images: Extract<Res<Assets<Image>>>,
+    cameras: Extract<Query<(&BlurRegionsCamera, Option<&Skybox>)>>,
+) {
+    cube_maps.0.clear();
+    for (camera, skybox) in &cameras {
+        let environment = camera.environment_map.as_ref().or(skybox.map(|skybox| &skybox.image));
+        let Some((id, image)) = environment.and_then(|handle| Some((handle.id(), images.get(handle)?))) else {
+            continue;
+        };
+        let dimension = image.texture_view_descriptor.as_ref().and_then(|descriptor| descriptor.dimension);
+        if dimension == Some(TextureViewDimension::Cube) {
+            cube_maps.0.push(id);
+        } else {
+            warn_once!(
+                "The environment map of a BlurRegionsCamera is not viewed as a cubemap, so its glass reflects \
+                nothing. Set the texture_view_descriptor of the image to a Cube dimension."
+            );
+        }
+    }
+}
+
+/// The views whose glass may reflect an environment map.
+type EnvironmentViewQueryData = (
+    Entity,
+    &'static BlurRegionsCamera,
+    &'static ExtractedView,
+    Option<&'static ExtractedCamera>,
+    Option<&'static Skybox>,
+);
+
+fn prepare_blur_regions_environments(
+    mut commands: Commands,
+    images: Res<RenderAssets<GpuImage>>,
+    cube_maps: Res<ExtractedBlurRegionsCubeMaps>,
+    views: Query<EnvironmentViewQueryData>,
+) {
+    for (entity, camera, view, extracted_camera, skybox) in &views {
+        // An explicit environment map wins over the skybox, which is rotated and lit the same way it is drawn
+        let exposure = extracted_camera.map_or(1.0, |extracted_camera| extracted_camera.exposure);
+        let environment = match (&camera.environment_map, skybox) {
+            (Some(image), _) => Some((image, Quat::IDENTITY, 1.0)),
+            (None, Some(skybox)) => Some((&skybox.image, skybox.rotation, skybox.brightness * exposure)),
+            (None, None) => None,
+        };
+        let Some((image, rotation, brightness)) = environment
+            .filter(|(image, _, _)| cube_maps.0.contains(&image.id()))
+            .and_then(|(image, rotation, brightness)| Some((images.get(image)?, rotation, brightness)))
+        else {
+            commands.entity(entity).remove::<ViewBlurRegionsEnvironment>();
+            continue;
+        };
+
+        commands.entity(entity).insert(ViewBlurRegionsEnvironment {
+            texture_view: image.texture_view.clone(),
+            sampler: image.sampler.clone(),
+            transform: Mat4::from_quat(rotation.inverse() * view.world_from_view.rotation()),
+            brightness,
+        });
+    }
+}
+
+fn prepare_blur_regions_textures(
+    mut commands: Commands,
+    images: Res<RenderAssets<GpuImage>>,
+    fallback_image: Res<FallbackImage>,
+    views: Query<(Entity, &BlurRegionsCamera)>,
+) {
+    let texture_view = |slots: &[AssetId<Image>], slot: usize| {
+        let image = slots.get(slot).and_then(|image| images.get(*image));
+        image.unwrap_or(&fallback_image.d2).texture_view.clone()
+    };
+    for (entity, camera) in &views {
+        commands.entity(entity).insert((
+            ViewBlurRegionsNormalMaps(std::array::from_fn(|slot| texture_view(&camera.normal_maps, slot))),
+            ViewBlurRegionsFrostMaps(std::array::from_fn(|slot| texture_view(&camera.frost_maps, slot))),
+        ));
+        if camera.frost_maps.iter().any(|image| images.get(*image).is_some()) {
+            commands.entity(entity).insert(ViewBlurRegionsFrosted);
+        } else {
+            commands.entity(entity).remove::<ViewBlurRegionsFrosted>();
+        }
+    }
+}
+
 impl SpecializedRenderPipeline for BlurRegionsPipeline {
     type Key = BlurRegionsPipelineKey;
 
     fn specialize(&self, key: Self::Key) -> RenderPipelineDescriptor {
-        let (layout, shader_defs) = match key.pass {
+        let (layout, mut shader_defs) = match key.pass {
             BlurRegionsPassKey::Horizontal => (
                 self.horizontal_layout.clone(),
                 vec!["HORIZONTAL_PASS".into()],
@@ -397,7 +1055,17 @@ impl SpecializedRenderPipeline for BlurRegionsPipeline {
                 self.vertical_layout.clone(),
                 vec!["VERTICAL_PASS".into()],
             ),
+            BlurRegionsPassKey::FrostBackdrop => (self.frost_backdrop_layout.clone(), vec![]),
         };
+        if key.hdr_lighting {
+            shader_defs.push("HDR_LIGHTING".into());
+        }
+        match key.kernel {
+            BlurRegionsKernel::Gaussian => {}
+            BlurRegionsKernel::Box => shader_defs.push("KERNEL_BOX".into()),
+            BlurRegionsKernel::Kawase => shader_defs.push("KERNEL_KAWASE".into()),
+            BlurRegionsKernel::Bokeh => shader_defs.push("KERNEL_BOKEH".into()),
+        }
 
         RenderPipelineDescriptor {
             label: Some("blur_regions_pipeline".into()),
@@ -408,11 +1076,15 @@ impl SpecializedRenderPipeline for BlurRegionsPipeline {
             multisample: MultisampleState::default(),
             push_constant_ranges: vec![],
             fragment: Some(FragmentState {
-                shader: get_shader_handle(),
+                shader: match key.pass {
+                    BlurRegionsPassKey::FrostBackdrop => frost_backdrop_shader_handle(),
+                    _ => get_shader_handle(),
+                },
                 shader_defs,
                 entry_point: match key.pass {
                     BlurRegionsPassKey::Horizontal => Some("horizontal".into()),
                     BlurRegionsPassKey::Vertical => Some("vertical".into()),
+                    BlurRegionsPassKey::FrostBackdrop => Some("downsample".into()),
                 },
                 targets: vec![Some(ColorTargetState {
                     format: if key.hdr {